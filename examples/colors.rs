@@ -3,39 +3,45 @@
 extern crate matrixise;
 use matrixise::*;
 
-static COLOR_PAIR_BLACK: i16 = 1;
-static COLOR_PAIR_GREEN: i16 = 2;
-static COLOR_PAIR_RED: i16 = 3;
-
 use std::time::Duration;
 
-fn main() {
-    // Here, we set up a white background
-    let mut screen = Scene::new(20, COLOR_WHITE, true, Duration::from_millis(20));
-    screen.init_pair(COLOR_PAIR_BLACK, COLOR_BLACK, COLOR_WHITE);
-    screen.init_pair(COLOR_PAIR_GREEN, COLOR_BLACK, COLOR_GREEN);
-    screen.init_pair(COLOR_PAIR_RED,   COLOR_BLUE,  COLOR_RED  );
+fn main() -> Result<(), Error> {
+    // Colors are asked for by what they look like, the palette picks pair numbers
+    let mut palette = Palette::new();
+    let head = palette.style(Style::new(COLOR_BLACK, COLOR_WHITE).bold())?;
+    let tail = palette.style(Style::new(COLOR_BLACK, COLOR_WHITE).dim())?;
 
-    screen.start(); // forks into a new thread
+    // Here, we set up a white background, with streaks that fade out from a bold head
+    let mut screen = Scene::builder()
+	.background(COLOR_WHITE)
+	.speed(Duration::from_millis(20))
+	.palette(palette)
+	.gradient(Gradient::new().head(head).fade(tail))
+	.build()?;
+
+    screen.start()?; // forks into a new thread
+
+    // Black text on white background, which shares a pair with the gradient
+    let black = screen.style(Style::new(COLOR_BLACK, COLOR_WHITE))?;
+    screen.push(Message::new_styled("Msg1", black, "0"))?;
 
-    // Black text on white background
-    screen.push(Message::new_with_title("Msg", "1", COLOR_PAIR_BLACK, "0"));
-    
-    // Black text on green background
-    screen.push(Message::new_with_title("Msg", "2", COLOR_PAIR_GREEN, "1"));
-    
     // Black text on green background
-    screen.push(Message::new_with_title("Msg", "3", COLOR_PAIR_RED, "2"));
+    let green = screen.style(Style::new(COLOR_BLACK, COLOR_GREEN))?;
+    screen.push(Message::new_styled("Msg2", green, "1"))?;
+
+    // Blue text on red background, bold
+    let red = screen.style(Style::new(COLOR_BLUE, COLOR_RED).bold())?;
+    screen.push(Message::new_styled("Msg3", red, "2"))?;
 
     // 7-color rainbow using the most basic curses colors
     let mut string = ColorString::new();
     for i in 0..7 {
-	screen.init_pair(i+4, COLOR_BLACK+i, COLOR_BLACK+i); // create a color pair
-	string.push(ColorChar::new(' ' as u32, COLOR_PAIR(i as u32+4))); // and use it
-	// Note: COLOR_PAIR() is used here because ColorChar can hold more than just
+	let color = screen.style(Style::new(COLOR_BLACK+i, COLOR_BLACK+i))?; // create a color pair
+	string.push(ColorChar::new(' ' as u32, color)); // and use it
+	// Note: styles come back as attributes because ColorChar can hold more than just
 	//       colors. It can handle bold, italic, and other pancurses attributes too
     }
-    screen.push(Message::new(string, "3")); // and turn it into a message
-    
-    screen.join(); // wait for screen to die (user presses q)
+    screen.push(Message::new(string, "3"))?; // and turn it into a message
+
+    screen.join() // wait for screen to die (user presses q)
 }
@@ -7,17 +7,22 @@ use std::time::Duration;
 
 const WHITE_PAIR : i16 = 1;
 
-fn main() {
+fn main() -> Result<(), Error> {
     // creating a screne with [max_padding = 20, black background, reuse messages, move down every 50ms]:
-    let mut screen = Scene::new(20, COLOR_BLACK, true, Duration::from_millis(50));
+    let mut screen = Scene::builder()
+	.max_padding(20)
+	.background(COLOR_BLACK)
+	.closed(true)
+	.speed(Duration::from_millis(50))
+	.build()?;
 
     // initalize test color
-    screen.init_pair(WHITE_PAIR, COLOR_WHITE, COLOR_BLACK);
+    screen.init_pair(WHITE_PAIR, COLOR_WHITE, COLOR_BLACK)?;
 
-    screen.start(); // forks into a new thread
+    screen.start()?; // forks into a new thread
     
     // Here's one way to add messages:
-    screen.push(Message::new_simple("Hello world", WHITE_PAIR, "0"));
+    screen.push(Message::new_simple("Hello world", WHITE_PAIR, "0"))?;
     
-    screen.join(); // wait for screen to die (user presses q)
+    screen.join() // wait for screen to die (user presses q)
 }
@@ -11,20 +11,26 @@ static COLOR_PAIR_WHITE: i16 = 1;
 use std::io::{self, BufRead};
 use std::time::Duration;
 
-fn main() {
+fn main() -> Result<(), Error> {
 
     // creating a screne with [max_padding = 20, black background, reuse messages]:
-    let mut screen = Scene::new(20, COLOR_BLACK, true, Duration::from_millis(25));
-    screen.init_pair(COLOR_PAIR_WHITE, COLOR_WHITE, COLOR_BLACK);
-    screen.start(); // forks into a new thread
+    // input can come in far faster than it rains, so only the newest 1000 lines are kept
+    // until the first line comes in, and between lines after that, flickering katakana rain fills the gaps
+    // each streak has a bold head and fades out toward its tail
+    let mut screen = Scene::builder().speed(Duration::from_millis(25)).capacity(1000).overflow(Overflow::DropOldest)
+	.filler(Charset::katakana()).mutation(0.05).gradient(Gradient::default()).build()?;
+    screen.init_pair(COLOR_PAIR_WHITE, COLOR_WHITE, COLOR_BLACK)?;
+    screen.start()?; // forks into a new thread
     
     let stdin = io::stdin();
     while screen.alive() {
 	for line in stdin.lock().lines() { // lock blocks q, so this it Control-C to exit
             let line = line.expect("Could not read line from standard in");
-            screen.push(Message::new_simple(&line, COLOR_PAIR_WHITE, "0"));
+            if screen.push(Message::new_simple(&line, COLOR_PAIR_WHITE, "0")).is_err() {
+		break; // render thread is gone
+	    }
 	}
     }
 
-    screen.kill();
+    screen.kill()
 }
@@ -0,0 +1,111 @@
+/*
+ * backend.rs
+ *
+ * Holds the Backend trait and its pancurses implementation:
+ *   Backend
+ *   CursesBackend
+ */
+
+use pancurses::{chtype, Window, Input, initscr, endwin, curs_set, noecho, has_colors, start_color, use_default_colors, init_pair, resize_term, COLOR_PAIR, COLOR_PAIRS};
+use crate::message::ColorChar;
+
+// Backend trait
+// Everything a Scene needs from whatever it draws on
+// Coordinates are (y, x) like curses, out of range cells are ignored
+pub trait Backend {
+    fn put(&mut self, y: i32, x: i32, glyph: ColorChar); // draw glyph with its attributes
+    fn clear(&mut self, y: i32, x: i32);                 // reset a cell to the background
+    fn size(&self) -> Option<(i32, i32)>;                // (height, width), None if unknown
+    fn flush(&mut self);                                 // make changes visible
+    fn poll(&mut self) -> Option<Input>;                 // non-blocking input check
+    fn erase(&mut self) {                                // clear everything
+    }
+    fn resize(&mut self, _height: i32, _width: i32) {    // dimensions changed under us
+    }
+    fn init_pair(&mut self, _pair: i16, _fg: i16, _bg: i16) { // color pairs are optional
+    }
+    fn max_pairs(&self) -> i16 {                         // pairs are numbered below this
+	256
+    }
+    fn set_background(&mut self, _pair: i16) {           // so is filling the screen with a pair's colours
+    }
+    fn shutdown(&mut self) {                             // restore whatever was taken over
+    }
+}
+
+// CursesBackend struct
+// Takes over the terminal with pancurses
+pub struct CursesBackend {
+    window: Window,
+}
+
+impl CursesBackend {
+    pub fn new() -> Self { // the scene sets the background once the palette has a pair for it
+	let window = initscr();
+
+	curs_set(0);
+	noecho();
+
+	if has_colors() {
+            start_color();
+	    use_default_colors(); // so -1 means the terminal's own colour, as Style promises
+	}
+
+	window.nodelay(true);
+
+	Self{window}
+    }
+}
+
+impl Default for CursesBackend {
+    fn default() -> Self {
+	Self::new()
+    }
+}
+
+impl Backend for CursesBackend {
+    fn put(&mut self, y: i32, x: i32, glyph: ColorChar) {
+	self.window.attron(glyph.attr);
+	match (glyph.cluster(), glyph.as_char()) {
+	    (Some(cluster), _) => { self.window.mvaddstr(y, x, cluster); }, // combining marks and all
+	    (None, Some(c)) if !c.is_ascii() => { self.window.mvaddstr(y, x, c.encode_utf8(&mut [0; 4])); }, // needs the wide curses
+	    _ => { self.window.mvaddch(y, x, glyph.data); },
+	}
+	self.window.attroff(glyph.attr);
+    }
+    fn clear(&mut self, y: i32, x: i32) {
+	self.window.mvaddch(y, x, self.window.getbkgd());
+    }
+    fn size(&self) -> Option<(i32, i32)> {
+	let (height, width) = self.window.get_max_yx();
+	if width == -1 {
+	    None
+	} else {
+	    Some((height, width))
+	}
+    }
+    fn flush(&mut self) {
+	self.window.refresh();
+    }
+    fn poll(&mut self) -> Option<Input> {
+	self.window.getch()
+    }
+    fn erase(&mut self) {
+	self.window.erase();
+    }
+    fn resize(&mut self, height: i32, width: i32) {
+	resize_term(height, width);
+    }
+    fn init_pair(&mut self, pair: i16, fg: i16, bg: i16) {
+	init_pair(pair, fg, bg);
+    }
+    fn max_pairs(&self) -> i16 { // only known once colours have started, 0 without them
+	COLOR_PAIRS().clamp(0, i16::MAX as i32) as i16
+    }
+    fn set_background(&mut self, pair: i16) {
+	self.window.bkgd(COLOR_PAIR(pair as chtype));
+    }
+    fn shutdown(&mut self) {
+	endwin();
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * backlog.rs
+ *
+ * Holds how full the queue may get, and what happens past that:
+ *   Overflow
+ *   PushStatus
+ *   Backlog
+ */
+
+use std::sync::{Mutex, MutexGuard, Condvar};
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+// Overflow enum
+// What a push does once the queue is at capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    Block,      // wait for the rain to make room, which a paused or unstarted scene never does, closed scenes can't use it
+    #[default]
+    DropOldest, // drop whatever is next in line in the lowest priority lane
+    DropNewest, // turn the new message away
+    Sample,     // keep a fair random sample of everything pushed since the queue filled up
+}
+
+// PushStatus enum
+// What happened to a pushed message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushStatus {
+    Queued,   // there was room
+    Replaced, // queued, and another message is dropped to make room
+    Dropped,  // turned away
+}
+
+// Backlog struct
+// Shared between a Scene and its render thread, counts messages queued and on their way
+// Pushes are decided here, so the pusher knows what happened without waiting on the render thread
+pub(crate) struct Backlog {
+    capacity: Option<usize>, // None never fills
+    overflow: Overflow,
+    load:     Mutex<Load>,
+    room:     Condvar,       // signalled whenever the queue changes
+}
+
+struct Load {
+    queued:  usize, // in the queue, as of the last time the render thread said
+    pending: usize, // sent, but not queued yet
+    offered: u64,   // pushes since the queue filled, for sampling
+    sampler: StdRng, // decides Overflow::Sample, seeded with the scene so samples repeat too
+}
+
+const BLOCK_CHECK: Duration = Duration::from_millis(50); // how often a blocked push checks the render thread is still there
+
+impl Backlog {
+    pub fn new(capacity: Option<usize>, overflow: Overflow, seed: Option<u64>) -> Self { // None seeds from entropy
+	let sampler = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+	Self{capacity, overflow, load: Mutex::new(Load{queued: 0, pending: 0, offered: 0, sampler}), room: Condvar::new()}
+    }
+    pub fn capacity(&self) -> Option<usize> {
+	self.capacity
+    }
+    pub fn overflow(&self) -> Overflow {
+	self.overflow
+    }
+    fn load(&self) -> MutexGuard<'_, Load> { // a panicking pusher shouldn't wedge everyone else
+	self.load.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    pub fn admit(&self) -> Option<PushStatus> { // one more message, None if it has to block
+	let mut load = self.load();
+	let capacity = match self.capacity {
+	    Some(capacity) if load.queued+load.pending >= capacity => capacity,
+	    _ => {
+		load.pending += 1;
+		load.offered = 0;
+		return Some(PushStatus::Queued);
+	    },
+	};
+	load.offered += 1;
+	let replaced = match self.overflow {
+	    Overflow::Block => return None,
+	    Overflow::DropOldest => true,
+	    Overflow::DropNewest => false,
+	    Overflow::Sample => {
+		let keep = capacity as f64/(capacity as f64+load.offered as f64); // later pushes are less likely to stay, so the sample stays even
+		load.sampler.gen_bool(keep)
+	    },
+	};
+	if replaced {
+	    load.pending += 1;
+	    Some(PushStatus::Replaced)
+	} else {
+	    Some(PushStatus::Dropped)
+	}
+    }
+    pub fn wait<F: Fn() -> bool>(&self, alive: F) -> bool { // until there might be room, false if the render thread is gone
+	let mut load = self.load();
+	while self.capacity.is_some_and(|capacity| load.queued+load.pending >= capacity) {
+	    if !alive() {
+		return false;
+	    }
+	    load = match self.room.wait_timeout(load, BLOCK_CHECK) {
+		Ok((load, _)) => load,
+		Err(poisoned) => poisoned.into_inner().0,
+	    };
+	}
+	true
+    }
+    pub fn arrived(&self, count: usize) { // render thread side, sent messages have come in
+	let mut load = self.load();
+	load.pending = load.pending.saturating_sub(count);
+    }
+    pub fn settle(&self, queued: usize) { // render thread side, after anything that changes the queue
+	let mut load = self.load();
+	if load.queued != queued {
+	    load.queued = queued;
+	    self.room.notify_all();
+	}
+    }
+}
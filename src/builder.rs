@@ -0,0 +1,213 @@
+/*
+ * builder.rs
+ *
+ * Holds SceneBuilder and the settings it hands to the render thread:
+ *   SceneBuilder
+ *   Config
+ */
+
+use std::time::Duration;
+use rand::{RngCore, SeedableRng};
+use rand::rngs::StdRng;
+use pancurses::{chtype, A_DIM, COLOR_BLACK};
+use crate::Scene;
+use crate::backend::{Backend, CursesBackend};
+use crate::error::Error;
+use crate::backlog::Overflow;
+use crate::charset::Charset;
+use crate::gradient::Gradient;
+use crate::keymap::Keymap;
+use crate::message::RecyclePolicy;
+use crate::palette::Palette;
+use crate::testing::VirtualScreen;
+
+// Config struct
+// Everything the render thread needs to know about how to rain
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub max_padding:     i32,              // most blank cells between two messages
+    pub closed:          bool,             // do we recycle messages?
+    pub recycle:         RecyclePolicy,    // in what order?
+    pub speed:           Duration,         // time between ticks
+    pub density:         f64,              // chance a free column spawns a streak on a tick
+    pub streak_length:   (f64, f64),       // range of streak lengths, in screen heights
+    pub capacity:        Option<usize>,    // most messages queued at once, None for no limit
+    pub overflow:        Overflow,         // what pushes do past that
+    pub filler:          Option<Charset>,  // what to show between messages, None for nothing
+    pub filler_attr:     chtype,           // and how
+    pub mutation:        f64,              // chance a filler cell re-rolls on a tick
+    pub mutate_messages: bool,             // do message cells flicker too?
+    pub gradient:        Option<Gradient>, // how streaks are styled from head to tail, None draws cells as they are
+    pub sample_seed:     Option<u64>,      // for Overflow::Sample, None seeds from entropy
+    pub background:      i16,              // curses colour behind everything, gets a pair from the palette
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), Error> {
+	Self::check_max_padding(self.max_padding)?;
+	check_color("background", self.background)?;
+	if !(self.density > 0.0 && self.density <= 1.0) {
+	    return Err(Error::InvalidConfig(format!("density must be in (0, 1], got {}", self.density)));
+	}
+	let (min, max) = self.streak_length;
+	if !(min >= 0.0 && min < max && max.is_finite()) {
+	    return Err(Error::InvalidConfig(format!("streak_length must satisfy 0 <= min < max, got {}..{}", min, max)));
+	}
+	Self::check_mutation(self.mutation)?;
+	if self.filler.as_ref().is_some_and(Charset::is_empty) {
+	    return Err(Error::InvalidConfig("filler charset must have at least one glyph".to_string()));
+	}
+	if self.capacity == Some(0) {
+	    return Err(Error::InvalidConfig("capacity must be at least 1".to_string()));
+	}
+	Self::check_blocking(self.closed, self.capacity, self.overflow)?;
+	Ok(())
+    }
+    pub fn check_mutation(rate: f64) -> Result<(), Error> {
+	if !(0.0..=1.0).contains(&rate) {
+	    return Err(Error::InvalidConfig(format!("mutation must be in [0, 1], got {}", rate)));
+	}
+	Ok(())
+    }
+    pub fn check_blocking(closed: bool, capacity: Option<usize>, overflow: Overflow) -> Result<(), Error> { // a closed queue never makes room
+	if closed && capacity.is_some() && overflow == Overflow::Block {
+	    return Err(Error::InvalidConfig("Overflow::Block needs closed(false), recycled messages never leave the queue".to_string()));
+	}
+	Ok(())
+    }
+    pub fn check_max_padding(max_padding: i32) -> Result<(), Error> {
+	if max_padding < 0 {
+	    return Err(Error::InvalidConfig(format!("max_padding must not be negative, got {}", max_padding)));
+	}
+	Ok(())
+    }
+}
+
+pub(crate) fn check_color(what: &str, color: i16) -> Result<(), Error> {
+    if color < -1 {
+	return Err(Error::InvalidConfig(format!("{} must be a curses color, got {}", what, color)));
+    }
+    Ok(())
+}
+
+impl Default for Config {
+    fn default() -> Self {
+	Self{max_padding: 20, closed: true, recycle: RecyclePolicy::RoundRobin, speed: Duration::from_millis(50), density: 1.0, streak_length: (0.1, 2.0), capacity: None, overflow: Overflow::DropOldest, filler: None, filler_attr: A_DIM, mutation: 0.0, mutate_messages: false, gradient: None, sample_seed: None, background: COLOR_BLACK}
+    }
+}
+
+// SceneBuilder struct
+// Named, validated settings for a Scene
+// Anything not set keeps its default:
+//   max_padding 20, black background, closed, round-robin recycling, 50ms ticks, density 1, streak_length 0.1..2,
+//   no capacity (dropping the oldest once one is set), no filler (dim once one is set),
+//   no mutation, and message text left alone when there is, no gradient, Keymap::default(), an empty Palette
+pub struct SceneBuilder {
+    config:     Config,
+    rng:        Option<Box<dyn RngCore + Send>>, // None seeds from entropy at build time
+    keymap:     Keymap,
+    palette:    Palette,
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+	Self{config: Config::default(), rng: None, keymap: Keymap::default(), palette: Palette::new()}
+    }
+    pub fn max_padding(mut self, max_padding: i32) -> Self {
+	self.config.max_padding = max_padding;
+	self
+    }
+    pub fn background(mut self, background: i16) -> Self {
+	self.config.background = background;
+	self
+    }
+    pub fn closed(mut self, closed: bool) -> Self { // recycle messages, or show each one once
+	self.config.closed = closed;
+	self
+    }
+    pub fn recycle(mut self, policy: RecyclePolicy) -> Self { // only matters while closed
+	self.config.recycle = policy;
+	self
+    }
+    pub fn speed(mut self, speed: Duration) -> Self {
+	self.config.speed = speed;
+	self
+    }
+    pub fn seed(mut self, seed: u64) -> Self { // same seed, same rain
+	self.rng = Some(Box::new(StdRng::seed_from_u64(seed)));
+	self.config.sample_seed = Some(seed);
+	self
+    }
+    pub fn rng<R: RngCore + Send + 'static>(mut self, mut rng: R) -> Self { // also seeds the sampling of Overflow::Sample
+	self.config.sample_seed = Some(rng.next_u64());
+	self.rng = Some(Box::new(rng));
+	self
+    }
+    pub fn density(mut self, density: f64) -> Self {
+	self.config.density = density;
+	self
+    }
+    pub fn streak_length(mut self, min: f64, max: f64) -> Self { // fractions of the screen height
+	self.config.streak_length = (min, max);
+	self
+    }
+    pub fn capacity(mut self, capacity: usize) -> Self { // bounds the queue, see overflow for what happens when it's full
+	self.config.capacity = Some(capacity);
+	self
+    }
+    pub fn overflow(mut self, overflow: Overflow) -> Self { // Overflow::Block only goes with closed(false)
+	self.config.overflow = overflow;
+	self
+    }
+    pub fn filler<C: Into<Charset>>(mut self, charset: C) -> Self { // rain even where there are no messages
+	self.config.filler = Some(charset.into());
+	self
+    }
+    pub fn filler_attr(mut self, attr: chtype) -> Self { // curses attributes for filler, A_DIM unless set
+	self.config.filler_attr = attr;
+	self
+    }
+    // chance each filler cell in view changes to another glyph of the filler charset, every tick
+    // does nothing without a filler
+    pub fn mutation(mut self, rate: f64) -> Self {
+	self.config.mutation = rate;
+	self
+    }
+    pub fn mutate_messages(mut self, mutate: bool) -> Self { // message text flickers at the same rate, false keeps it legible
+	self.config.mutate_messages = mutate;
+	self
+    }
+    pub fn gradient(mut self, gradient: Gradient) -> Self { // bright heads and fading tails, see Gradient::default
+	self.config.gradient = Some(gradient);
+	self
+    }
+    pub fn keymap(mut self, keymap: Keymap) -> Self { // defaults to Keymap::default()
+	self.keymap = keymap;
+	self
+    }
+    pub fn palette(mut self, palette: Palette) -> Self { // its pairs are set up before anything is drawn
+	self.palette = palette;
+	self
+    }
+    pub fn build(self) -> Result<Scene, Error> { // starts a render thread drawing with pancurses
+	self.build_with_backend(CursesBackend::new)
+    }
+    // backend is built inside the render thread, as curses windows can't be sent between threads
+    pub fn build_with_backend<B, F>(self, backend: F) -> Result<Scene, Error>
+    where B: Backend, F: FnOnce() -> B + Send + 'static {
+	self.config.validate()?;
+	let rng = self.rng.unwrap_or_else(|| Box::new(StdRng::from_entropy()));
+	Scene::spawn(self.config, self.keymap, self.palette, rng, backend)
+    }
+    pub fn build_virtual(self, height: i32, width: i32) -> Result<VirtualScreen, Error> { // no thread, no terminal
+	self.config.validate()?;
+	let rng = self.rng.unwrap_or_else(|| Box::new(StdRng::from_entropy()));
+	Ok(VirtualScreen::spawn(self.config, self.keymap, self.palette, rng, height, width))
+    }
+}
+
+impl Default for SceneBuilder {
+    fn default() -> Self {
+	Self::new()
+    }
+}
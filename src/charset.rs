@@ -0,0 +1,92 @@
+/*
+ * charset.rs
+ *
+ * Holds the Charset struct, alphabets to draw generated glyphs from
+ */
+
+use std::iter::FromIterator;
+use rand::{Rng, RngCore};
+
+// Charset struct
+// A set of glyphs, each equally likely to be picked
+// Built-ins can be combined with each other and with user sets through union
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Charset {
+    glyphs: Vec<char>,
+}
+
+impl Charset {
+    pub fn new<I: IntoIterator<Item = char>>(glyphs: I) -> Self { // repeats are dropped, so they don't skew the odds
+	let mut charset = Self{glyphs: Vec::new()};
+	charset.extend(glyphs);
+	charset
+    }
+    fn range(first: char, last: char) -> Self {
+	Self::new(first..=last)
+    }
+    pub fn katakana() -> Self { // half-width, U+FF66 to U+FF9D, like the films
+	Self::range('\u{FF66}', '\u{FF9D}')
+    }
+    pub fn latin() -> Self { // A to Z and a to z
+	Self::range('A', 'Z').union(Self::range('a', 'z'))
+    }
+    pub fn digits() -> Self {
+	Self::range('0', '9')
+    }
+    pub fn binary() -> Self {
+	Self::new("01".chars())
+    }
+    pub fn hex() -> Self { // 0 to 9 and A to F
+	Self::digits().union(Self::range('A', 'F'))
+    }
+    pub fn box_drawing() -> Self { // U+2500 to U+257F
+	Self::range('\u{2500}', '\u{257F}')
+    }
+    pub fn dna() -> Self { // A, C, G and T
+	Self::new("ACGT".chars())
+    }
+    pub fn union(mut self, other: Charset) -> Self {
+	self.extend(other.glyphs);
+	self
+    }
+    pub fn glyphs(&self) -> &[char] {
+	&self.glyphs
+    }
+    pub fn len(&self) -> usize {
+	self.glyphs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+	self.glyphs.is_empty()
+    }
+    pub fn contains(&self, glyph: char) -> bool {
+	self.glyphs.contains(&glyph)
+    }
+    pub(crate) fn pick(&self, rng: &mut dyn RngCore) -> char { // ' ' if empty
+	match self.glyphs.len() {
+	    0 => ' ',
+	    len => self.glyphs[rng.gen_range(0, len)],
+	}
+    }
+}
+
+impl Extend<char> for Charset {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, glyphs: I) {
+	for glyph in glyphs {
+	    if !self.glyphs.contains(&glyph) {
+		self.glyphs.push(glyph);
+	    }
+	}
+    }
+}
+
+impl FromIterator<char> for Charset {
+    fn from_iter<I: IntoIterator<Item = char>>(glyphs: I) -> Self {
+	Self::new(glyphs)
+    }
+}
+
+impl From<&str> for Charset {
+    fn from(glyphs: &str) -> Self {
+	Self::new(glyphs.chars())
+    }
+}
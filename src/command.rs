@@ -0,0 +1,109 @@
+/*
+ * command.rs
+ *
+ * Holds what Scene and VirtualScreen can both be told:
+ *   Commands
+ */
+
+use std::time::Duration;
+use pancurses::{chtype, Input, COLOR_BLACK, COLOR_PAIR};
+use crate::ThreadMsg;
+use crate::backlog::Backlog;
+use crate::builder::{check_color, Config};
+use crate::error::Error;
+use crate::id::Id;
+use crate::keymap::{Action, Keymap};
+use crate::message::{Message, RecyclePolicy};
+use crate::palette::{Palette, Style};
+
+// Commands trait
+// Checks a command and turns it into a ThreadMsg, so a new knob is written once for both
+// Implementors only say how a ThreadMsg gets to their render loop, and where their palette and backlog live
+pub(crate) trait Commands {
+    fn send(&mut self, thread_msg: ThreadMsg) -> Result<(), Error>;
+    fn palette_mut(&mut self) -> &mut Palette;
+    fn backlog(&self) -> &Backlog;
+
+    fn push_update(&mut self, message: Message) -> Result<(), Error> {
+	self.send(ThreadMsg::PushUpdate(message))
+    }
+    fn append_update(&mut self, messages: Vec<Message>) -> Result<(), Error> {
+	self.send(ThreadMsg::AppendUpdate(messages))
+    }
+    fn set_speed(&mut self, speed: Duration) -> Result<(), Error> {
+	self.send(ThreadMsg::Speed(speed))
+    }
+    fn set_max_padding(&mut self, max_padding: i32) -> Result<(), Error> {
+	Config::check_max_padding(max_padding)?;
+	self.send(ThreadMsg::MaxPadding(max_padding))
+    }
+    fn set_mutation(&mut self, rate: f64) -> Result<(), Error> {
+	Config::check_mutation(rate)?;
+	self.send(ThreadMsg::Mutation(rate))
+    }
+    fn set_background(&mut self, background: i16) -> Result<(), Error> {
+	let pair = self.pair(COLOR_BLACK, background)?;
+	self.send(ThreadMsg::Background(pair))
+    }
+    fn set_closed(&mut self, closed: bool) -> Result<(), Error> {
+	Config::check_blocking(closed, self.backlog().capacity(), self.backlog().overflow())?;
+	self.send(ThreadMsg::Closed(closed))
+    }
+    fn set_recycle(&mut self, policy: RecyclePolicy) -> Result<(), Error> {
+	self.send(ThreadMsg::Recycle(policy))
+    }
+    fn bind(&mut self, key: Input, action: Action) -> Result<(), Error> {
+	self.send(ThreadMsg::Bind(key, Some(action)))
+    }
+    fn unbind(&mut self, key: Input) -> Result<(), Error> {
+	self.send(ThreadMsg::Bind(key, None))
+    }
+    fn set_keymap(&mut self, keymap: Keymap) -> Result<(), Error> {
+	self.send(ThreadMsg::Keymap(keymap))
+    }
+    fn pause(&mut self) -> Result<(), Error> {
+	self.send(ThreadMsg::Pause)
+    }
+    fn resume(&mut self) -> Result<(), Error> {
+	self.send(ThreadMsg::Resume)
+    }
+    fn step(&mut self, ticks: usize) -> Result<(), Error> {
+	self.send(ThreadMsg::Step(ticks))
+    }
+    fn remove(&mut self, id: Id) -> Result<(), Error> {
+	self.send(ThreadMsg::Remove(id))
+    }
+    fn remove_group(&mut self, prefix: Id) -> Result<(), Error> {
+	self.send(ThreadMsg::RemoveGroup(prefix))
+    }
+    fn remove_where<F>(&mut self, predicate: F) -> Result<(), Error>
+    where F: FnMut(&Message) -> bool + Send + 'static {
+	self.send(ThreadMsg::RemoveWhere(Box::new(predicate)))
+    }
+    fn update_group<F>(&mut self, prefix: Id, update: F) -> Result<(), Error>
+    where F: FnMut(&mut Message) + Send + 'static {
+	self.send(ThreadMsg::UpdateGroup(prefix, Box::new(update)))
+    }
+    fn clear(&mut self) -> Result<(), Error> {
+	self.send(ThreadMsg::Clear)
+    }
+    fn init_pair(&mut self, pair: i16, c1: i16, c2: i16) -> Result<(), Error> {
+	check_color("foreground", c1)?;
+	check_color("background", c2)?;
+	if self.palette_mut().claim(pair)? {
+	    self.send(ThreadMsg::ColorPair(pair, c1, c2))?;
+	}
+	Ok(())
+    }
+    fn style(&mut self, style: Style) -> Result<chtype, Error> {
+	let pair = self.pair(style.fg, style.bg)?;
+	Ok(COLOR_PAIR(pair as chtype) | style.attrs)
+    }
+    fn pair(&mut self, fg: i16, bg: i16) -> Result<i16, Error> { // from the palette, set up on the render thread if it's new
+	let (pair, new) = self.palette_mut().allocate(fg, bg)?;
+	if new {
+	    self.send(ThreadMsg::ColorPair(pair, fg, bg))?;
+	}
+	Ok(pair)
+    }
+}
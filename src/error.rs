@@ -0,0 +1,34 @@
+/*
+ * error.rs
+ *
+ * Holds the Error enum, everything a Scene can report back
+ */
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    InvalidConfig(String), // a setting is out of range, says which one
+    ScreenSize,            // the backend couldn't tell how big the screen is
+    AlreadyStarted,        // Scene::start was called twice
+    Disconnected,          // the render thread has already exited
+    Panicked,              // the render thread panicked
+    PaletteFull,           // every colour pair is taken
+    PairTaken(i16),        // a pair set up by hand belongs to the background or the palette
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	match self {
+	    Error::InvalidConfig(reason) => write!(f, "invalid scene configuration: {}", reason),
+	    Error::ScreenSize     => write!(f, "could not get screen size"),
+	    Error::AlreadyStarted => write!(f, "tried to start screen twice"),
+	    Error::Disconnected   => write!(f, "the render thread has exited"),
+	    Error::Panicked       => write!(f, "the render thread panicked"),
+	    Error::PaletteFull    => write!(f, "no colour pairs left"),
+	    Error::PairTaken(pair) => write!(f, "colour pair {} is already in use", pair),
+	}
+    }
+}
+
+impl std::error::Error for Error {}
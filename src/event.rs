@@ -0,0 +1,54 @@
+/*
+ * event.rs
+ *
+ * Holds what the render thread tells the application:
+ *   SceneEvent
+ *   ExitReason
+ */
+
+use std::sync::mpsc::Sender;
+use pancurses::Input;
+use crate::error::Error;
+use crate::id::Id;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneEvent {
+    Key(Input),                        // a key the scene had no use for
+    Resize{height: i32, width: i32},   // the screen changed size
+    StreakSpawned{id: Id, column: i32}, // message id started falling in column
+    MessageDisplayed(Id),              // every character of message id has been drawn, never sent for text cut off by the screen edges
+    QueueEmpty,                        // nothing left waiting to be shown
+    Exit(ExitReason),                  // the render thread is done, always the last event
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitReason {
+    Quit,         // the user pressed a quit key
+    Killed,       // Scene::kill
+    Disconnected, // the Scene was dropped
+    Error(Error), // something went wrong, Scene::join has it too
+}
+
+// Events struct
+// Where the render thread sends events, if anyone asked for them
+pub(crate) struct Events {
+    tx: Option<Sender<SceneEvent>>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+	Self{tx: None}
+    }
+    pub fn subscribe(&mut self, tx: Sender<SceneEvent>) { // replaces any earlier listener
+	self.tx = Some(tx);
+    }
+    pub fn emit(&mut self, event: SceneEvent) {
+	let gone = match self.tx.as_ref() {
+	    Some(tx) => tx.send(event).is_err(),
+	    None => false,
+	};
+	if gone { // receiver was dropped, stop bothering
+	    self.tx = None;
+	}
+    }
+}
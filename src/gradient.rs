@@ -0,0 +1,62 @@
+/*
+ * gradient.rs
+ *
+ * Holds the Gradient struct, how a streak is styled from head to tail
+ */
+
+use pancurses::{chtype, A_BOLD, A_COLOR, A_DIM, A_NORMAL};
+use crate::message::ColorChar;
+
+// Gradient struct
+// The head is the lowest cell of a streak, the rest is split evenly between the fade stages, brightest first
+// Stages are curses attributes, such as COLOR_PAIR(2) | A_BOLD, and replace whatever a cell had
+// Message cells with a colour pair of their own keep it when message_colors is set, only taking the other attributes
+// Gaps with nothing in them, not even filler, are left alone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    head:           Option<chtype>, // None leaves the head as it is
+    fade:           Vec<chtype>,    // empty leaves the tail as it is
+    message_colors: bool,
+}
+
+impl Gradient {
+    pub fn new() -> Self { // no highlight and no fade, message colours win once there are
+	Self{head: None, fade: Vec::new(), message_colors: true}
+    }
+    pub fn head(mut self, attr: chtype) -> Self {
+	self.head = Some(attr);
+	self
+    }
+    pub fn fade(mut self, attr: chtype) -> Self { // one more stage, further from the head than the last
+	self.fade.push(attr);
+	self
+    }
+    pub fn message_colors(mut self, keep: bool) -> Self {
+	self.message_colors = keep;
+	self
+    }
+    // glyph is from_head cells above the head of a streak length long
+    pub(crate) fn apply(&self, glyph: &ColorChar, from_head: i32, length: i32, message: bool) -> ColorChar {
+	let stage = if from_head <= 0 {
+	    self.head
+	} else if self.fade.is_empty() {
+	    None
+	} else {
+	    let n = self.fade.len() as i32;
+	    Some(self.fade[((from_head-1)*n/length.max(1)).clamp(0, n-1) as usize])
+	};
+	let attr = match stage {
+	    None => glyph.attr,
+	    Some(stage) if message && self.message_colors && glyph.attr & A_COLOR != 0 => glyph.attr | (stage & !A_COLOR),
+	    Some(stage) if message && self.message_colors => glyph.attr | stage,
+	    Some(stage) => stage,
+	};
+	glyph.with_attr(attr)
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self { // bold head, then normal, then dim
+	Self::new().head(A_BOLD).fade(A_NORMAL).fade(A_DIM)
+    }
+}
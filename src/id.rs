@@ -0,0 +1,111 @@
+/*
+ * id.rs
+ *
+ * Holds the Id struct, what messages are known by
+ */
+
+use std::fmt;
+
+// Id struct
+// A path of segments, such as "host1/cpu" or ("host1", "cpu")
+// Anything turned into segments is split on '/', strings, tuple members and child alike,
+// so ("host1", 3) is "host1/3", and ("host1/cpu", 0) is the same id as "host1/cpu/0"
+// Group operations take a prefix, "host1" covers "host1/cpu" but not "host10/cpu"
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id {
+    segments: Vec<String>,
+}
+
+impl Id {
+    pub fn new() -> Self { // no segments, a prefix of every id
+	Self{segments: Vec::new()}
+    }
+    pub fn child<S: ToString>(mut self, segment: S) -> Self { // further down, by as many segments as it has
+	self.segments.extend(split(&segment.to_string()).map(str::to_string));
+	self
+    }
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+	self.segments.iter().map(String::as_str)
+    }
+    pub fn starts_with(&self, prefix: &Id) -> bool { // whole segments only
+	self.segments.starts_with(&prefix.segments)
+    }
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> { // "" is the empty id, not one empty segment
+    path.split('/').filter(move |_| !path.is_empty())
+}
+
+impl From<&str> for Id {
+    fn from(path: &str) -> Self {
+	Self{segments: split(path).map(str::to_string).collect()}
+    }
+}
+
+impl From<String> for Id {
+    fn from(path: String) -> Self {
+	Self::from(path.as_str())
+    }
+}
+
+impl From<&String> for Id {
+    fn from(path: &String) -> Self {
+	Self::from(path.as_str())
+    }
+}
+
+impl From<&Id> for Id {
+    fn from(id: &Id) -> Self {
+	id.clone()
+    }
+}
+
+macro_rules! id_from_segment {
+    ($($t:ty),*) => {$(
+	impl From<$t> for Id {
+	    fn from(segment: $t) -> Self {
+		Self::new().child(segment)
+	    }
+	}
+    )*}
+}
+
+id_from_segment!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, char);
+
+macro_rules! id_from_tuple {
+    ($($t:ident $segment:ident),*) => {
+	impl<$($t: fmt::Display),*> From<($($t,)*)> for Id {
+	    fn from(($($segment,)*): ($($t,)*)) -> Self {
+		Self::new()$(.child($segment))*
+	    }
+	}
+    }
+}
+
+id_from_tuple!(A a, B b);
+id_from_tuple!(A a, B b, C c);
+id_from_tuple!(A a, B b, C c, D d);
+
+impl PartialEq<str> for Id {
+    fn eq(&self, path: &str) -> bool {
+	self.segments().eq(split(path))
+    }
+}
+
+impl PartialEq<&str> for Id {
+    fn eq(&self, path: &&str) -> bool {
+	self == *path
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "{}", self.segments.join("/"))
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	write!(f, "Id({:?})", self.to_string())
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * keymap.rs
+ *
+ * Holds what keys do while a scene is running:
+ *   Keymap
+ *   Action
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use pancurses::Input;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,   // end the scene
+    Pause,  // pause, or resume if already paused
+    Step,   // pause, then move forward one tick
+    Faster, // shorten the tick by a fifth
+    Slower, // lengthen the tick by a quarter
+    Clear,  // same as Scene::clear, drop every message, queued or falling
+}
+
+pub(crate) enum Binding {
+    Action(Action),
+    Handler(Box<dyn FnMut(Input) + Send>), // runs on the render thread
+}
+
+// Keymap struct
+// Maps keys to built-in actions or handlers
+// Keys with no binding become SceneEvent::Key, unless forwarding is turned off
+// Resizes are always handled by the scene and can't be bound
+pub struct Keymap {
+    bindings: HashMap<Input, Binding>,
+    forward:  bool,
+}
+
+impl Keymap {
+    pub fn new() -> Self { // no bindings at all, not even quit
+	Self{bindings: HashMap::new(), forward: true}
+    }
+    pub fn bind(mut self, key: Input, action: Action) -> Self {
+	self.bindings.insert(key, Binding::Action(action));
+	self
+    }
+    pub fn on<F: FnMut(Input) + Send + 'static>(mut self, key: Input, handler: F) -> Self {
+	self.bindings.insert(key, Binding::Handler(Box::new(handler)));
+	self
+    }
+    pub fn unbind(mut self, key: Input) -> Self {
+	self.bindings.remove(&key);
+	self
+    }
+    pub fn forward_unbound(mut self, forward: bool) -> Self {
+	self.forward = forward;
+	self
+    }
+    pub(crate) fn insert(&mut self, key: Input, action: Option<Action>) { // for changes at runtime
+	match action {
+	    Some(action) => self.bindings.insert(key, Binding::Action(action)),
+	    None => self.bindings.remove(&key),
+	};
+    }
+    pub(crate) fn get_mut(&mut self, key: &Input) -> Option<&mut Binding> {
+	self.bindings.get_mut(key)
+    }
+    pub(crate) fn forwards(&self) -> bool {
+	self.forward
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self { // q and delete quit, p pauses, space steps
+	Self::new()
+	    .bind(Input::Character('q'), Action::Quit)
+	    .bind(Input::KeyDC, Action::Quit)
+	    .bind(Input::Character('p'), Action::Pause)
+	    .bind(Input::Character(' '), Action::Step)
+    }
+}
+
+impl fmt::Debug for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	f.debug_map()
+	 .entries(self.bindings.iter().map(|(key, binding)| (key, match binding {
+	     Binding::Action(action) => format!("{:?}", action),
+	     Binding::Handler(_) => "handler".to_string(),
+	 })))
+	 .finish()
+    }
+}
@@ -1,21 +1,51 @@
+mod id;
+pub use crate::id::Id;
 mod message;
 use crate::message::MessageQueue;
-pub use crate::message::{Message, ColorString, ColorChar};
+pub use crate::message::{Message, Priority, RecyclePolicy, ColorString, ColorChar};
 mod streak;
 use crate::streak::Streak;
+mod backend;
+pub use crate::backend::{Backend, CursesBackend};
+pub mod testing;
+mod error;
+pub use crate::error::Error;
+mod backlog;
+pub use crate::backlog::{Overflow, PushStatus};
+use crate::backlog::Backlog;
+mod charset;
+pub use crate::charset::Charset;
+mod gradient;
+pub use crate::gradient::Gradient;
+mod palette;
+pub use crate::palette::{Palette, Style};
+mod builder;
+pub use crate::builder::SceneBuilder;
+use crate::builder::Config;
+mod event;
+pub use crate::event::{SceneEvent, ExitReason};
+use crate::event::Events;
+mod keymap;
+pub use crate::keymap::{Keymap, Action};
+use crate::keymap::Binding;
+mod command;
+use crate::command::Commands;
 
 pub use pancurses::*;
 
 extern crate rand;
-use rand::Rng;
+use rand::{Rng, RngCore};
 
-use std::sync::mpsc::{self, TryRecvError};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError, RecvTimeoutError};
 use std::sync::{Arc, Weak};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
+const INPUT_POLL: Duration = Duration::from_millis(10); // longest the render thread sleeps without checking keys
+
 enum ThreadMsg {
     Kill,
     Start,
@@ -23,147 +53,409 @@ enum ThreadMsg {
     PushUpdate(Message),
     Append(Vec<Message>),
     AppendUpdate(Vec<Message>),
-    ColorPair(i16, i16, i16)
+    Remove(Id),
+    RemoveGroup(Id),
+    RemoveWhere(Box<dyn FnMut(&Message) -> bool + Send>),
+    UpdateGroup(Id, Box<dyn FnMut(&mut Message) + Send>),
+    Clear,
+    ColorPair(i16, i16, i16),
+    Subscribe(mpsc::Sender<SceneEvent>),
+    Speed(Duration),
+    MaxPadding(i32),
+    Mutation(f64),
+    Background(i16),
+    Closed(bool),
+    Recycle(RecyclePolicy),
+    Pause,
+    Resume,
+    Step(usize),
+    Bind(Input, Option<Action>),
+    Keymap(Keymap),
 }
 
-struct ForkedScene { // the version of Scene that lives in another thread
+struct ForkedScene<B: Backend> { // the version of Scene that lives in another thread
     columns:     Vec<Column>,  // holds all streaks in the scene
     height:      i32,          // height of the scene
     queue:       MessageQueue, // Messages yet to be printed
-    max_padding: i32,
+    config:      Config,
     rx:          Option<std::sync::mpsc::Receiver<ThreadMsg>>,
     started:     bool,
-    speed:       Duration,
+    paused:      bool,         // started, but the clock isn't moving streaks
     last_updated:Instant,
-    window:      Window,
+    backend:     B,
+    rng:         Box<dyn RngCore + Send>, // every random decision comes from here
+    shut_down:   bool,         // has the backend been given back?
+    events:      Events,       // what we tell the application
+    keymap:      Keymap,       // what keys do
+    drained:     bool,         // was the queue empty last tick?
+    backlog:     Arc<Backlog>, // how full the queue is, shared with the Scene
 }
-impl ForkedScene {
-    pub fn new(max_padding: i32, background: i16, is_closed: bool, rx: std::sync::mpsc::Receiver<ThreadMsg>, speed: Duration) -> Self {
-	let window = initscr();
-	
-	curs_set(0);
-	noecho();
-
-	if has_colors() {
-            start_color();
-	}
-	
-	window.nodelay(true);
-	init_pair(99, COLOR_BLACK, background);
-	window.bkgd(COLOR_PAIR(99));
-
-	let (height, width) = window.get_max_yx();
-	
-	if width == -1 {
-	    panic!("Could not get screen size!");
-	}
+impl<B: Backend> ForkedScene<B> {
+    // backlog is shared with whoever admits pushes, see Scene::push
+    pub fn new(config: Config, keymap: Keymap, backlog: Arc<Backlog>, rx: Option<std::sync::mpsc::Receiver<ThreadMsg>>, mut backend: B, rng: Box<dyn RngCore + Send>) -> Result<Self, Error> {
+	let (height, width) = match backend.size() {
+	    Some((height, width)) if height >= 0 && width >= 0 => (height, width),
+	    _ => {
+		backend.shutdown();
+		return Err(Error::ScreenSize);
+	    }
+	};
 	let mut columns = Vec::with_capacity(width as usize);
 	for _ in 0..width {
 	    columns.push(Column::new());
 	}
-	Self{columns, height, queue: MessageQueue::new(width as usize, is_closed), max_padding, rx: Some(rx), started: false, speed, last_updated: Instant::now(), window: window}
-    }
-    pub fn kill(&self){
-	endwin();
-    }
-    pub fn update(&mut self) -> bool {
-	if self.rx.is_none() {
-	    return false;
-	}
-	let rx = self.rx.as_ref().unwrap();
-        match rx.try_recv() {
-	    Err(TryRecvError::Disconnected) => {
-                self.kill();
-                return false;
-	    }
-	    Ok(thread_msg) => {
-		match thread_msg {
-		    ThreadMsg::Start => {
-			if self.started == true {
-			    panic!("Tried to start screen twice!");
-			} else {
-			    self.start();
-			}
-		    }
-		    ThreadMsg::Push(message) => {
-			self.queue.push(message);
-		    }
-		    ThreadMsg::PushUpdate(message) => {
-			self.queue.push_update(message);
-		    }
-		    ThreadMsg::Append(messages) => {
-			self.queue.append(messages.into());
-		    }
-		    ThreadMsg::AppendUpdate(messages) => {
-			self.queue.append_update(messages.into());
-		    }
-		    ThreadMsg::ColorPair(pair, c1, c2) => {
-			init_pair(pair, c1, c2);
-		    }
-		    ThreadMsg::Kill => {
-			self.kill();
+	Ok(Self{columns, height, queue: MessageQueue::new(width as usize, config.closed, config.recycle), config, rx, started: false, paused: false, last_updated: Instant::now(), backend, rng, shut_down: false, events: Events::new(), keymap, drained: false, backlog})
+    }
+    pub fn kill(&mut self){
+	if !self.shut_down {
+	    self.backend.shutdown();
+	    self.shut_down = true;
+	}
+    }
+    pub fn exit(&mut self, reason: ExitReason) { // kill, and say why
+	if !self.shut_down {
+	    self.events.emit(SceneEvent::Exit(reason));
+	}
+	self.kill();
+    }
+    pub fn update(&mut self) -> bool { // handle everything waiting, then tick if it's time
+	loop {
+	    let received = match self.rx.as_ref() {
+		Some(rx) => rx.try_recv(),
+		None => return false,
+	    };
+	    match received {
+		Err(TryRecvError::Disconnected) => {
+		    self.exit(ExitReason::Disconnected);
+		    return false;
+		}
+		Ok(thread_msg) => {
+		    if !self.handle(thread_msg) {
 			return false; // make sure main exits
 		    }
 		}
+		Err(TryRecvError::Empty) => break,
 	    }
-	    Err(TryRecvError::Empty) => {}
 	}
-	if self.started && self.last_updated.elapsed() >= self.speed {
+	if self.ticking() && self.last_updated.elapsed() >= self.config.speed {
 	    self.last_updated = Instant::now();
 	    self.advance();
 	}
 	true
     }
+    fn ticking(&self) -> bool { // is the clock moving streaks?
+	self.started && !self.paused
+    }
+    fn timeout(&self) -> Duration { // how long wait() may sleep
+	let timeout = INPUT_POLL; // keys still have to be polled
+	if self.ticking() {
+	    timeout.min((self.last_updated + self.config.speed).saturating_duration_since(Instant::now()))
+	} else {
+	    timeout
+	}
+    }
+    pub fn wait(&mut self) -> bool { // sleep until the next tick or ThreadMsg, whichever comes first
+	let timeout = self.timeout();
+	let received = match self.rx.as_ref() {
+	    Some(rx) => rx.recv_timeout(timeout),
+	    None => return false,
+	};
+	match received {
+	    Ok(thread_msg) => self.handle(thread_msg),
+	    Err(RecvTimeoutError::Timeout) => true,
+	    Err(RecvTimeoutError::Disconnected) => {
+		self.exit(ExitReason::Disconnected);
+		false
+	    }
+	}
+    }
+    fn handle(&mut self, thread_msg: ThreadMsg) -> bool { // false if the scene should exit
+	match thread_msg {
+	    ThreadMsg::Start => {
+		if !self.started { // Scene::start already refuses a second start
+		    self.start();
+		}
+	    }
+	    ThreadMsg::Push(message) => {
+		self.backlog.arrived(1);
+		self.enqueue(Arc::new(message), false);
+	    }
+	    ThreadMsg::PushUpdate(message) => {
+		let message = Arc::new(message);
+		self.refresh(std::slice::from_ref(&message));
+		self.enqueue(message, true);
+	    }
+	    ThreadMsg::Append(messages) => {
+		self.backlog.arrived(messages.len());
+		messages.into_iter().for_each(|message| self.enqueue(Arc::new(message), false));
+	    }
+	    ThreadMsg::AppendUpdate(messages) => {
+		let messages: Vec<Arc<Message>> = messages.into_iter().map(Arc::new).collect();
+		self.refresh(&messages);
+		messages.into_iter().for_each(|message| self.enqueue(message, true));
+	    }
+	    ThreadMsg::Remove(id) => {
+		self.queue.remove(&id);
+		self.rerender_where(|streak| streak.remove_where(|message| message.id == id));
+	    }
+	    ThreadMsg::RemoveGroup(prefix) => {
+		self.remove_where(|message| message.id.starts_with(&prefix));
+	    }
+	    ThreadMsg::RemoveWhere(predicate) => {
+		self.remove_where(predicate);
+	    }
+	    ThreadMsg::UpdateGroup(prefix, mut update) => {
+		let queued: HashMap<Id, Arc<Message>> = self.queue.update_where(|message| message.id.starts_with(&prefix), &mut update)
+		    .into_iter().map(|message| (message.id.clone(), message)).collect();
+		self.refresh_where(|old| match queued.get(&old.id) { // falling text that already left the queue is updated too
+		    Some(new) => Some(new.clone()),
+		    None if old.id.starts_with(&prefix) => Some(Arc::new(old.updated_with(&mut update))),
+		    None => None,
+		});
+	    }
+	    ThreadMsg::Clear => {
+		self.queue.clear();
+		self.rerender_where(|streak| streak.remove_where(|_| true));
+	    }
+	    ThreadMsg::ColorPair(pair, c1, c2) => {
+		self.backend.init_pair(pair, c1, c2);
+	    }
+	    ThreadMsg::Speed(speed) => {
+		self.config.speed = speed; // wait() picks this up right away
+	    }
+	    ThreadMsg::MaxPadding(max_padding) => {
+		self.config.max_padding = max_padding; // applies to new streaks
+	    }
+	    ThreadMsg::Mutation(rate) => {
+		self.config.mutation = rate;
+	    }
+	    ThreadMsg::Background(pair) => {
+		self.backend.set_background(pair);
+	    }
+	    ThreadMsg::Closed(closed) => {
+		self.config.closed = closed;
+		self.queue.set_closed(closed);
+	    }
+	    ThreadMsg::Recycle(policy) => {
+		self.config.recycle = policy;
+		self.queue.set_policy(policy);
+	    }
+	    ThreadMsg::Pause => {
+		self.paused = true;
+	    }
+	    ThreadMsg::Resume => {
+		if self.paused {
+		    self.paused = false;
+		    self.last_updated = Instant::now(); // next tick is a full tick away
+		}
+	    }
+	    ThreadMsg::Step(ticks) => {
+		self.paused = true;
+		for _ in 0..ticks {
+		    self.advance();
+		}
+	    }
+	    ThreadMsg::Bind(key, action) => {
+		self.keymap.insert(key, action);
+	    }
+	    ThreadMsg::Keymap(keymap) => {
+		self.keymap = keymap;
+	    }
+	    ThreadMsg::Subscribe(tx) => {
+		self.events.subscribe(tx);
+	    }
+	    ThreadMsg::Kill => {
+		self.exit(ExitReason::Killed);
+		return false;
+	    }
+	}
+	self.backlog.settle(self.queue.len()); // blocked pushes might have room now
+	true
+    }
+    // pushes were already let in by the Backlog, make room if they were told we would
+    // updates weren't, as only we know if they add a message, so those get the overflow policy here
+    fn enqueue(&mut self, message: Arc<Message>, update: bool) {
+	let grows = !(update && self.queue.contains(&message.id));
+	if grows && self.backlog.capacity().is_some_and(|capacity| self.queue.len() >= capacity) {
+	    match self.backlog.overflow() {
+		Overflow::DropOldest => self.queue.drop_oldest(),
+		Overflow::Sample => self.queue.drop_random(&mut *self.rng),
+		Overflow::Block | Overflow::DropNewest if update => return, // can't block in here, so turn it away
+		Overflow::Block | Overflow::DropNewest => (),
+	    }
+	}
+	if update {
+	    self.queue.push_update(message);
+	} else {
+	    self.queue.push(message);
+	}
+    }
     fn start(&mut self) {
-	self.window.refresh();
+	self.backend.flush();
 	self.last_updated = Instant::now();
 	self.started = true;
     }
+    pub fn input(&mut self, input: Input) -> Result<bool, Error> { // false if the scene should exit
+	if input == Input::KeyResize {
+	    if let Err(error) = self.resize() {
+		self.exit(ExitReason::Error(error.clone()));
+		return Err(error);
+	    }
+	    return Ok(true);
+	}
+	let action = match self.keymap.get_mut(&input) {
+	    Some(Binding::Action(action)) => *action,
+	    Some(Binding::Handler(handler)) => {
+		handler(input);
+		return Ok(true);
+	    },
+	    None => {
+		if self.keymap.forwards() {
+		    self.events.emit(SceneEvent::Key(input)); // it's for the application
+		}
+		return Ok(true);
+	    },
+	};
+	match action {
+	    Action::Quit => {
+		self.exit(ExitReason::Quit);
+		return Ok(false);
+	    },
+	    Action::Pause => {
+		self.handle(if self.paused {ThreadMsg::Resume} else {ThreadMsg::Pause});
+	    },
+	    Action::Step => {
+		self.handle(ThreadMsg::Step(1));
+	    },
+	    Action::Faster => {
+		self.config.speed = self.config.speed*4/5;
+	    },
+	    Action::Slower => {
+		self.config.speed = (self.config.speed*5/4).max(self.config.speed+Duration::from_millis(1)); // don't get stuck at 0
+	    },
+	    Action::Clear => {
+		self.handle(ThreadMsg::Clear);
+	    },
+	}
+	Ok(true)
+    }
     pub fn advance(&mut self){ // move all streaks, clean up dead ones, try to spawn new ones
-	let mut rng = rand::thread_rng();
-	let untouched = self.columns.iter().fold(0, |sum, column| sum + if column.touched  {0} else {1}); // counting untouched to make it progressively more likely to spawn a streak
+	let rng = &mut self.rng;
+	let width = self.columns.len() as i32;
+	let untouched = self.columns.iter().filter(|column| !column.touched).count() as i32; // counting untouched to make it progressively more likely to spawn a streak
+	let mut covered = Vec::new(); // rows the column to the left spills into
 	for (i, column) in self.columns.iter_mut().enumerate() {
 	    for streak in &mut column.streaks { // advance all
-		streak.derender(&self.window);
+		streak.derender(&mut self.backend, self.height, &covered);
 		streak.advance();
 	    }
 	    let height = self.height; // always fighting with the borrow checker
 	    column.streaks.retain(|streak| !streak.finished(height)); // clean up dead streaks
 
 	    // now, try to spawn new streaks
-	    if column.streaks.len()==0 || column.streaks.iter().all(|streak| streak.top_space() > 5) { // check if there's need to
-		if (!column.touched && rng.gen_range(0, untouched) == 0) || column.touched { // if we started recently, thin things out to look better
+	    if (column.streaks.is_empty() || column.streaks.iter().all(|streak| streak.top_space() > 5)) // check if there's need to
+		&& (column.touched || rng.gen_range(0, untouched) == 0) // if we started recently, thin things out to look better
+		&& rng.gen_bool(self.config.density) {
 		// add new streak, consuming from queue
-		column.add_streak(Streak::new_with_queue(&mut self.queue, i as i32, rng.gen_range(self.height/10, self.height*2), self.height, self.max_padding));
-		    
+		let (min, max) = self.config.streak_length;
+		let shortest = (self.height as f64*min) as i32;
+		let length = rng.gen_range(shortest, ((self.height as f64*max) as i32).max(shortest+1));
+		let filler_attr = self.config.filler_attr;
+		let filler = self.config.filler.as_ref().map(|filler| (filler, filler_attr));
+		let streak = Streak::new_with_queue(&mut self.queue, rng, i as i32, length, self.height, self.config.max_padding, filler);
+		for id in streak.ids() {
+		    self.events.emit(SceneEvent::StreakSpawned{id: id.clone(), column: i as i32});
 		}
+		column.add_streak(streak);
 	    }
 
 	    
 	    for streak in &mut column.streaks { // advance all
-		streak.render(&self.window);
+		let mutation = self.config.mutation;
+		if let Some(charset) = self.config.filler.as_ref().filter(|_| mutation > 0.0) {
+		    streak.mutate(rng, charset, mutation, self.config.mutate_messages);
+		}
+		streak.render(&mut self.backend, self.height, width, self.config.gradient.as_ref(), &covered);
+		for id in streak.take_displayed() {
+		    self.events.emit(SceneEvent::MessageDisplayed(id));
+		}
+	    }
+	    covered = column.covered(self.height, width, &covered);
+	}
+	self.backend.flush();
+	self.backlog.settle(self.queue.len());
+	if self.queue.is_empty() != self.drained { // only say so when it first runs dry
+	    self.drained = !self.drained;
+	    if self.drained {
+		self.events.emit(SceneEvent::QueueEmpty);
 	    }
 	}
-	self.window.refresh();
     }
-    pub fn resize(&mut self) {
+    pub fn resize(&mut self) -> Result<(), Error> {
 	// first, update the term
-	let height = self.window.get_max_y();
-	let width  = self.window.get_max_x();
-	if width == -1 {
-	    panic!("Could not get screen size!");
-	}
+	let (height, width) = match self.backend.size() {
+	    Some((height, width)) if height >= 0 && width >= 0 => (height, width),
+	    _ => return Err(Error::ScreenSize),
+	};
 	self.height = height;
-	resize_term(height, width);
-	self.window.erase();
-	self.window.refresh();
-	// Then, update columns
-	let drained = self.queue.drain();
-	self.queue.append(drained);
-	self.columns = Vec::with_capacity(width as usize);
-	for _ in 0..width {
+	self.backend.resize(height, width);
+	self.events.emit(SceneEvent::Resize{height, width});
+	// Then, update columns, keeping streaks that still fit
+	self.columns.truncate(width as usize);
+	while self.columns.len() < width as usize {
 	    self.columns.push(Column::new());
 	}
+	for column in &mut self.columns {
+	    column.streaks.retain(|streak| !streak.finished(height));
+	}
+	self.redraw();
+	Ok(())
+    }
+    fn refresh(&mut self, messages: &[Arc<Message>]) { // text already falling shows updates straight away
+	let updates: HashMap<&Id, &Arc<Message>> = messages.iter().map(|message| (&message.id, message)).collect(); // later ones win
+	self.refresh_where(|old| updates.get(&old.id).map(|&new| new.clone()));
+    }
+    fn refresh_where<F: FnMut(&Message) -> Option<Arc<Message>>>(&mut self, mut updated: F) { // swap falling messages for what updated returns
+	self.rerender_where(|streak| streak.refresh(&mut updated));
+    }
+    fn remove_where<F: FnMut(&Message) -> bool>(&mut self, mut predicate: F) { // from the queue, and stop any already falling
+	self.queue.remove_where(&mut predicate);
+	self.rerender_where(|streak| streak.remove_where(&mut predicate));
+    }
+    fn rerender_where<F: FnMut(&mut Streak) -> bool>(&mut self, mut change: F) { // draw again any streak change says it changed
+	let width = self.columns.len() as i32;
+	let mut changed = false;
+	let mut covered = Vec::new();
+	for column in &mut self.columns {
+	    for streak in &mut column.streaks {
+		if change(streak) {
+		    streak.render(&mut self.backend, self.height, width, self.config.gradient.as_ref(), &covered);
+		    changed = true;
+		}
+	    }
+	    covered = column.covered(self.height, width, &covered);
+	}
+	if changed {
+	    self.backend.flush();
+	}
+    }
+    fn redraw(&mut self) { // draw everything from scratch, so a paused scene survives a resize
+	self.backend.erase();
+	let width = self.columns.len() as i32;
+	let mut covered = Vec::new();
+	for column in &self.columns {
+	    for streak in &column.streaks {
+		streak.render(&mut self.backend, self.height, width, self.config.gradient.as_ref(), &covered);
+	    }
+	    covered = column.covered(self.height, width, &covered);
+	}
+	self.backend.flush();
+    }
+}
+
+impl<B: Backend> Drop for ForkedScene<B> {
+    fn drop(&mut self) { // also runs when the render thread panics, so the terminal is never left behind
+	self.kill();
     }
 }
 
@@ -176,82 +468,247 @@ impl ForkedScene {
 
 pub struct Scene {
     tx:              Option<std::sync::mpsc::Sender<ThreadMsg>>, // used to communicate with other threads
-    join_handle:     Option<JoinHandle<()>>, // needed for rejoining
+    join_handle:     Option<JoinHandle<Result<(), Error>>>, // needed for rejoining, says how the thread ended
     thread_control:  Option<Weak<AtomicBool>>, // needed for seeing if the thread is still alive
+    started:         bool,
+    backlog:         Arc<Backlog>, // how full the render thread's queue is
+    palette:         Palette,      // colour pairs handed out so far
 }
 
 impl Scene {
-    pub fn new(max_padding: i32, background: i16, is_closed: bool, speed: Duration) -> Self {
+    pub fn builder() -> SceneBuilder {
+	SceneBuilder::new()
+    }
+    fn spawn<B, F>(config: Config, keymap: Keymap, palette: Palette, rng: Box<dyn RngCore + Send>, backend: F) -> Result<Self, Error>
+    where B: Backend, F: FnOnce() -> B + Send + 'static {
 	let (tx, rx) = mpsc::channel();
+	let (ready_tx, ready_rx) = mpsc::channel(); // tells us if the backend came up
 
+	let backlog = Arc::new(Backlog::new(config.capacity, config.overflow, config.sample_seed));
+	let shared = backlog.clone();
+	let background = config.background;
 	let working = Arc::new(AtomicBool::new(true));
 	let control = Arc::downgrade(&working);
 
 	let join_handle = thread::spawn(move || {
 	    
-	    let mut background = ForkedScene::new(max_padding, background, is_closed, rx, speed);
+	    let mut background = match ForkedScene::new(config, keymap, shared, Some(rx), backend(), rng) {
+		Ok(background) => background,
+		Err(error) => {
+		    let _ = ready_tx.send(Err(error.clone()));
+		    return Err(error);
+		}
+	    };
+	    let _ = ready_tx.send(Ok(background.backend.max_pairs()));
 	    while (*working).load(Ordering::Relaxed) {
 		if !background.update() {
 		    break;
 		}
-		match background.window.getch() {
-		    Some(Input::Character(c)) => { if c == 'q' {
-			background.kill();
+		if let Some(input) = background.backend.poll() {
+		    if !background.input(input)? {
 			break;
-		    } },
-		    Some(Input::KeyDC) => break,
-		    Some(Input::KeyResize) => background.resize(),
-		    Some(_) => { panic!("I don't know what to do with this!"); },
-		    None => ()
+		    }
+		}
+		if !background.wait() {
+		    break;
+		}
+	    }
+	    Ok(())
+	});
+
+	match ready_rx.recv() {
+	    Ok(Ok(max_pairs)) => {
+		let mut palette = palette;
+		let limited = palette.limit(max_pairs).and_then(|_| palette.pair(COLOR_BLACK, background));
+		let background = match limited {
+		    Ok(pair) => pair,
+		    Err(error) => {
+			let _ = tx.send(ThreadMsg::Kill);
+			let _ = join_handle.join();
+			return Err(error);
+		    }
+		};
+		for (pair, fg, bg) in palette.pairs().filter(|_| max_pairs > 0) {
+		    let _ = tx.send(ThreadMsg::ColorPair(pair, fg, bg));
 		}
-	    }});
-	
-	Self{tx: Some(tx), join_handle: Some(join_handle), thread_control: Some(control)}
+		let _ = tx.send(ThreadMsg::Background(background));
+		Ok(Self{tx: Some(tx), join_handle: Some(join_handle), thread_control: Some(control), started: false, backlog, palette})
+	    },
+	    Ok(Err(error)) => {
+		let _ = join_handle.join();
+		Err(error)
+	    }
+	    Err(_) => Err(Error::Panicked), // died before the backend was up
+	}
     }
-    pub fn push(&mut self, message: Message){
-	if !self.alive() {
-	    return;
+    // what happened to the message, which only matters with a capacity, see SceneBuilder::capacity
+    // blocks for Overflow::Block while the queue is full
+    pub fn push(&mut self, message: Message) -> Result<PushStatus, Error> {
+	let status = self.admit()?;
+	if status != PushStatus::Dropped {
+	    self.send(ThreadMsg::Push(message))?;
 	}
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::Push(message));
+	Ok(status)
     }
-    pub fn push_update(&mut self, message: Message){
-	if !self.alive() {
-	    return;
+    // updates are never turned away here, as only the render thread knows whether one adds a message
+    // one that does gets the overflow policy there, and is dropped for Block and DropNewest
+    pub fn push_update(&mut self, message: Message) -> Result<(), Error> {
+	Commands::push_update(self, message)
+    }
+    pub fn append(&mut self, messages: Vec<Message>) -> Result<Vec<PushStatus>, Error> { // one status per message, in order
+	let mut statuses = Vec::with_capacity(messages.len());
+	let mut admitted = Vec::with_capacity(messages.len());
+	for message in messages {
+	    let status = match self.backlog.admit() {
+		Some(status) => status,
+		None => { // full, so send what we have to make room for
+		    if !admitted.is_empty() {
+			self.send(ThreadMsg::Append(std::mem::take(&mut admitted)))?;
+		    }
+		    self.admit()?
+		}
+	    };
+	    if status != PushStatus::Dropped {
+		admitted.push(message);
+	    }
+	    statuses.push(status);
+	}
+	if !admitted.is_empty() {
+	    self.send(ThreadMsg::Append(admitted))?;
 	}
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::PushUpdate(message));
+	Ok(statuses)
     }
-    pub fn append(&mut self, messages: Vec<Message>){
+    pub fn append_update(&mut self, messages: Vec<Message>) -> Result<(), Error> { // same as push_update
+	Commands::append_update(self, messages)
+    }
+    fn admit(&self) -> Result<PushStatus, Error> { // waits for room if it has to
 	if !self.alive() {
-	    return;
+	    return Err(Error::Disconnected);
+	}
+	loop {
+	    if let Some(status) = self.backlog.admit() {
+		return Ok(status);
+	    }
+	    if !self.backlog.wait(|| self.alive()) {
+		return Err(Error::Disconnected);
+	    }
 	}
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::Append(messages));
     }
-    pub fn append_update(&mut self, messages: Vec<Message>){
-	if !self.alive() {
-	    return;
+    pub fn set_speed(&mut self, speed: Duration) -> Result<(), Error> {
+	Commands::set_speed(self, speed)
+    }
+    pub fn set_max_padding(&mut self, max_padding: i32) -> Result<(), Error> {
+	Commands::set_max_padding(self, max_padding)
+    }
+    pub fn set_mutation(&mut self, rate: f64) -> Result<(), Error> { // see SceneBuilder::mutation
+	Commands::set_mutation(self, rate)
+    }
+    pub fn set_background(&mut self, background: i16) -> Result<(), Error> { // gets a pair from the palette, like a style would
+	Commands::set_background(self, background)
+    }
+    pub fn set_closed(&mut self, closed: bool) -> Result<(), Error> { // recycle messages from now on, or stop, refused with Overflow::Block
+	Commands::set_closed(self, closed)
+    }
+    pub fn set_recycle(&mut self, policy: RecyclePolicy) -> Result<(), Error> {
+	Commands::set_recycle(self, policy)
+    }
+    pub fn bind(&mut self, key: Input, action: Action) -> Result<(), Error> {
+	Commands::bind(self, key, action)
+    }
+    pub fn unbind(&mut self, key: Input) -> Result<(), Error> {
+	Commands::unbind(self, key)
+    }
+    pub fn set_keymap(&mut self, keymap: Keymap) -> Result<(), Error> { // replaces every binding and handler
+	Commands::set_keymap(self, keymap)
+    }
+    pub fn pause(&mut self) -> Result<(), Error> { // freeze on the current frame, pushes still get queued
+	Commands::pause(self)
+    }
+    pub fn resume(&mut self) -> Result<(), Error> {
+	Commands::resume(self)
+    }
+    pub fn step(&mut self, ticks: usize) -> Result<(), Error> { // pause, then move forward ticks frames
+	Commands::step(self, ticks)
+    }
+    // Removing takes messages out of the queue, and blanks them out of streaks already falling
+    pub fn remove<I: Into<Id>>(&mut self, id: I) -> Result<(), Error> { // every message with this id
+	Commands::remove(self, id.into())
+    }
+    pub fn remove_group<I: Into<Id>>(&mut self, prefix: I) -> Result<(), Error> { // every message with an id under prefix
+	Commands::remove_group(self, prefix.into())
+    }
+    pub fn remove_where<F>(&mut self, predicate: F) -> Result<(), Error>
+    where F: FnMut(&Message) -> bool + Send + 'static {
+	Commands::remove_where(self, predicate)
+    }
+    // change every message with an id under prefix, queued or falling
+    // ids can't be changed this way, anything else can
+    pub fn update_group<I, F>(&mut self, prefix: I, update: F) -> Result<(), Error>
+    where I: Into<Id>, F: FnMut(&mut Message) + Send + 'static {
+	Commands::update_group(self, prefix.into(), update)
+    }
+    pub fn clear(&mut self) -> Result<(), Error> { // drop every message, queued or falling, streaks of filler keep falling
+	Commands::clear(self)
+    }
+    pub fn start(&mut self) -> Result<(), Error> { // start and fork to background
+	if self.started {
+	    return Err(Error::AlreadyStarted);
 	}
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::AppendUpdate(messages));
+	let sent = self.tx.as_ref().map(|tx| tx.send(ThreadMsg::Start).is_ok()).unwrap_or(false);
+	if !sent { // the thread is gone, find out why
+	    self.join()?;
+	    return Err(Error::Disconnected);
+	}
+	self.started = true;
+	Ok(())
+    }
+    // the palette won't hand this pair out afterwards
+    // pairs the palette has handed out, the background's included, are refused, set those up through style instead
+    pub fn init_pair(&mut self, pair: i16, c1: i16, c2: i16) -> Result<(), Error> {
+	Commands::init_pair(self, pair, c1, c2)
     }
-    pub fn start(&mut self){ // start and fork to background
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::Start);
+    pub fn style(&mut self, style: Style) -> Result<chtype, Error> { // attributes for style, setting up a colour pair if it needs one
+	Commands::style(self, style)
     }
-    pub fn init_pair(&self, pair: i16, c1: i16, c2: i16){
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::ColorPair(pair.into(), c1, c2));	
+    pub fn palette(&self) -> &Palette {
+	&self.palette
+    }
+    pub fn events(&mut self) -> Receiver<SceneEvent> { // listen to the render thread, replaces any earlier receiver
+	let (tx, rx) = mpsc::channel();
+	if let Some(scene_tx) = self.tx.as_ref() {
+	    let _ = scene_tx.send(ThreadMsg::Subscribe(tx));
+	}
+	rx
     }
     pub fn alive(&self) -> bool { // ping the background thread to see if it's alive
 	self.thread_control.is_some() && self.thread_control.as_ref().unwrap().upgrade().is_some()
     }
-    pub fn kill(&mut self){
-	if !self.alive() {
-	    return;
+    pub fn kill(&mut self) -> Result<(), Error> {
+	if let Some(tx) = self.tx.take() {
+	    let _ = tx.send(ThreadMsg::Kill);
 	}
-	let _ = self.tx.as_ref().unwrap().send(ThreadMsg::Kill);
-	let _ = self.join_handle.take().unwrap().join(); // give curses time to clean up
-	self.join_handle = None;
-	self.tx = None;
+	self.join() // give curses time to clean up
     }
-    pub fn join(&self) {
-	while self.alive() {} // wait till screen is dead
+    pub fn join(&mut self) -> Result<(), Error> { // wait till screen is dead, and hear how it died
+	match self.join_handle.take() {
+	    Some(handle) => handle.join().unwrap_or(Err(Error::Panicked)),
+	    None => Ok(()),
+	}
+    }
+}
+
+impl Commands for Scene {
+    fn send(&mut self, thread_msg: ThreadMsg) -> Result<(), Error> {
+	match self.tx.as_ref() {
+	    Some(tx) if self.alive() => tx.send(thread_msg).map_err(|_| Error::Disconnected),
+	    _ => Err(Error::Disconnected),
+	}
+    }
+    fn palette_mut(&mut self) -> &mut Palette {
+	&mut self.palette
+    }
+    fn backlog(&self) -> &Backlog {
+	&self.backlog
     }
 }
 
@@ -269,6 +726,14 @@ impl Column {
 	self.streaks.push(streak);
 	self.touched = true;
     }
+    // rows where a wide glyph here takes the next column's cell too, given the rows left covers here
+    fn covered(&self, screen_height: i32, screen_width: i32, left: &[bool]) -> Vec<bool> {
+	let mut covered = vec![false; screen_height.max(0) as usize];
+	for streak in &self.streaks {
+	    streak.cover(&mut covered, screen_width, left);
+	}
+	covered
+    }
 }
 
 #[cfg(test)]
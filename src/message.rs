@@ -3,80 +3,468 @@
  *
  * Holds the Message struct and other important structs and aliases:
  *   ColorStringQueue
+ *   Weights
+ *   RecyclePolicy
+ *   Priority
  *   ColorString
  *   ColorChar
  */
 
 use std::fmt;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use rand::{Rng, RngCore};
+use crate::id::Id;
 use pancurses::{chtype, A_BOLD, COLOR_PAIR};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 // MessageQueue struct
-// Holds many messages waiting to be used
+// Holds many messages waiting to be used, one lane per Priority
+// Lanes take turns by weight, so a busy lane can't starve the ones under it
+// Messages sit in slots that never move, lanes only hold slot numbers,
+// so recycling shuffles numbers around and an id always finds its slot in one lookup
+// Slots sharing an id are chained oldest to newest, so any of them leaves in constant time however many there are
+// Messages are shared with the streaks showing them, so popping never copies text
+// Every policy picks without walking a lane: weighted picks search Weights,
+// and least recently shown keeps its lanes in the order they were shown, so the front is always next
+// Removing by id doesn't walk a lane either, its slots are marked gone and left for a pop to clear out
 pub struct MessageQueue {
-    data: VecDeque<Message>,
-    pub closed: bool,            // Do we recycle data?
+    slots:   Vec<Option<Slot>>,           // None once freed
+    free:    Vec<usize>,                  // freed slots, reused before growing
+    index:   HashMap<Id, usize>,          // id to the newest slot holding it, which links to the rest
+    lanes:   Vec<VecDeque<usize>>,        // slot numbers, indexed by Priority
+    weights: Vec<Weights>,                // pick weights of each lane's slots, indexed by Priority
+    credits: [u32; LANES],                // pops each lane has left this round
+    shows:   u64,                         // pops so far, stamps Message::last_shown
+    gone:    usize,                       // slots removed by id and still in a lane
+    closed:  bool,                        // Do we recycle data?
+    policy:  RecyclePolicy,               // and if so, in what order?
 }
 
+// Slot struct
+// A queued message and how it's been shown so far
+struct Slot {
+    message:    Arc<Message>,
+    shown:      u32,           // times popped into a streak
+    last_shown: u64,           // when this was last popped, 0 for never
+    older:      Option<usize>, // the slot pushed before this one with the same id
+    newer:      Option<usize>, // and after
+    weight:     f64,           // what this slot adds to its lane's Weights
+    gone:       bool,          // removed by id, so out of the index and the weights but not yet out of its lane
+}
+
+impl Slot {
+    fn spent(&self, now: Instant) -> bool { // should the queue drop this?
+	self.gone || self.message.expires.is_some_and(|deadline| now >= deadline) || self.message.max_shows.is_some_and(|max_shows| self.shown >= max_shows)
+    }
+}
+
+// Weights struct
+// A Fenwick tree of pick weights by slot number, so a weighted pick is a descent rather than a walk
+// Slots outside the lane weigh nothing, and it grows as the queue's slots do
+struct Weights {
+    tree: Vec<f64>, // node i sums the weights of slots i+1-lowbit(i+1) to i
+}
+
+impl Weights {
+    fn new() -> Self {
+	Self{tree: Vec::new()}
+    }
+    fn add(&mut self, slot: usize, weight: f64) {
+	while self.tree.len() <= slot {
+	    let node = self.tree.len()+1;
+	    let covered = self.prefix(node-1) - self.prefix(node - (node & node.wrapping_neg())); // the slots under it so far
+	    self.tree.push(covered);
+	}
+	let mut node = slot+1;
+	while node <= self.tree.len() {
+	    self.tree[node-1] += weight;
+	    node += node & node.wrapping_neg();
+	}
+    }
+    fn prefix(&self, mut node: usize) -> f64 { // total weight of the slots below node
+	let mut total = 0.0;
+	while node > 0 {
+	    total += self.tree[node-1];
+	    node -= node & node.wrapping_neg();
+	}
+	total
+    }
+    fn total(&self) -> f64 {
+	self.prefix(self.tree.len())
+    }
+    fn find(&self, mut target: f64) -> Option<usize> { // the slot whose share of the total holds target
+	let mut slot = 0;
+	let mut step = self.tree.len().checked_next_power_of_two().unwrap_or(0);
+	while step > 0 {
+	    if slot+step <= self.tree.len() && self.tree[slot+step-1] <= target {
+		slot += step;
+		target -= self.tree[slot-1];
+	    }
+	    step /= 2;
+	}
+	if slot < self.tree.len() {
+	    Some(slot)
+	} else {
+	    None
+	}
+    }
+    fn clear(&mut self) {
+	self.tree.clear();
+    }
+}
+
+// RecyclePolicy enum
+// Which message a closed queue shows next from a lane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecyclePolicy {
+    #[default]
+    RoundRobin,         // in order, shown messages go to the back
+    Shuffled,           // shown messages go back somewhere random, never straight to the front
+    WeightedRandom,     // any message, more likely the higher its weight
+    LeastRecentlyShown, // whichever has waited longest, new messages first
+}
+
+const LANES: usize = 4;
+const LANE_WEIGHTS: [u32; LANES] = [1, 2, 4, 8]; // pops per round, Low to Critical
+                                                  // a new critical message waits at most 7 pops
+
 impl MessageQueue {
-    pub fn new(capacity: usize, closed: bool) -> Self {
-	Self{data: VecDeque::with_capacity(capacity), closed}
+    pub fn new(capacity: usize, closed: bool, policy: RecyclePolicy) -> Self {
+	let mut lanes: Vec<VecDeque<usize>> = (0..LANES).map(|_| VecDeque::new()).collect();
+	lanes[Priority::Normal as usize].reserve(capacity);
+	let weights = (0..LANES).map(|_| Weights::new()).collect();
+	Self{slots: Vec::with_capacity(capacity), free: Vec::new(), index: HashMap::with_capacity(capacity), lanes, weights, credits: LANE_WEIGHTS, shows: 0, gone: 0, closed, policy}
+    }
+    pub fn set_closed(&mut self, closed: bool) {
+	self.closed = closed;
+	self.reorder();
+    }
+    pub fn set_policy(&mut self, policy: RecyclePolicy) {
+	self.policy = policy;
+	self.reorder();
+    }
+    fn by_last_shown(&self) -> bool { // are lanes kept least recently shown first?
+	self.closed && self.policy == RecyclePolicy::LeastRecentlyShown
+    }
+    fn last_shown(&self, slot: usize) -> u64 {
+	self.slots[slot].as_ref().map_or(0, |entry| entry.last_shown)
+    }
+    fn reorder(&mut self) { // sort lanes into the order by_last_shown needs, the one slow step of switching to it
+	if !self.by_last_shown() {
+	    return;
+	}
+	let slots = &self.slots;
+	for lane in &mut self.lanes {
+	    lane.make_contiguous().sort_by_key(|&slot| slots[slot].as_ref().map_or(0, |entry| entry.last_shown));
+	}
+    }
+    fn enqueue(&mut self, lane: usize, slot: usize) { // at the back, or for by_last_shown among the others shown as long ago
+	let back = if self.by_last_shown() {
+	    let last_shown = self.last_shown(slot);
+	    self.lanes[lane].partition_point(|&other| self.last_shown(other) <= last_shown)
+	} else {
+	    self.lanes[lane].len()
+	};
+	self.lanes[lane].insert(back, slot);
+    }
+    fn unqueue(&mut self, lane: usize, slot: usize) { // out of its lane, cheap when it's at the front
+	let lane = &mut self.lanes[lane];
+	if lane.front() == Some(&slot) {
+	    lane.pop_front();
+	} else if let Some(i) = lane.iter().position(|&other| other == slot) {
+	    lane.remove(i);
+	}
     }
-    pub fn pop(&mut self) -> Option<Message> { // pop from queue, check if we need to recycle
-	let message_check = self.data.front(); // front() is used so data doesn't leave
-	if message_check.is_none() {           // until it's safe (for updating)
+    pub fn pop(&mut self, rng: &mut dyn RngCore) -> Option<Arc<Message>> { // pop from queue, check if we need to recycle
+	let now = Instant::now();
+	loop {
+	    let lane = self.next_lane()?;
+	    let slot = self.pick(lane, rng)?;
+	    let entry = self.slots[slot].as_mut()?; // data doesn't leave until it's safe (for updating)
+	    if entry.spent(now) {
+		self.unqueue(lane, slot); // expired, or shown enough
+		self.release(slot);
+		continue;
+	    }
+	    self.credits[lane] -= 1;
+	    self.shows += 1;
+	    entry.shown += 1;
+	    entry.last_shown = self.shows;
+	    let recycle = (self.closed || entry.message.max_shows.is_some()) && !entry.spent(now);
+	    if !recycle {
+		self.unqueue(lane, slot);
+		return self.release(slot);
+	    }
+	    let message = entry.message.clone();
+	    if self.closed && self.policy == RecyclePolicy::WeightedRandom {
+		return Some(message); // stays where it is
+	    }
+	    self.unqueue(lane, slot); // the front, for every other policy
+	    let lane = &mut self.lanes[lane];
+	    let back = if self.closed && self.policy == RecyclePolicy::Shuffled && !lane.is_empty() {
+		rng.gen_range(1, lane.len()+1) // not first, so it can't come straight back
+	    } else {
+		lane.len() // for by_last_shown too, as it's just been shown
+	    };
+	    lane.insert(back, slot);
+	    return Some(message);
+	}
+    }
+    fn pick(&self, lane: usize, rng: &mut dyn RngCore) -> Option<usize> { // which slot of a waiting lane is next
+	let front = self.lanes[lane].front().copied();
+	if !self.closed || self.policy != RecyclePolicy::WeightedRandom {
+	    return front; // open queues are first in, first out, and closed ones keep their lanes in order
+	}
+	let weights = &self.weights[lane];
+	let total = weights.total();
+	if !(total > 0.0 && total.is_finite()) {
+	    return front;
+	}
+	let target = rng.gen_range(0.0, total);
+	weights.find(target).filter(|&slot| self.slots[slot].is_some()).or(front) // rounding can leave a sliver past the end
+    }
+    fn next_lane(&mut self) -> Option<usize> { // highest waiting lane with turns left, starting a new round if none have
+	if self.is_empty() {
+	    self.clear(); // nothing but gone slots, if anything
 	    return None;
 	}
-	
-	if self.closed {
-	    self.push(self.data.front().unwrap().clone());
+	let lanes = &self.lanes;
+	let credits = &mut self.credits;
+	let waiting = |credits: &[u32; LANES]| (0..LANES).rev().find(|&lane| !lanes[lane].is_empty() && credits[lane] > 0);
+	waiting(credits).or_else(|| {
+	    *credits = LANE_WEIGHTS;
+	    waiting(credits)
+	})
+    }
+    fn store(&mut self, message: Arc<Message>) -> usize { // into a slot, which is indexed but not in a lane yet
+	let id = message.id.clone();
+	let older = self.index.get(&id).copied();
+	let (lane, weight) = (message.priority as usize, message.pick_weight());
+	let entry = Some(Slot{message, shown: 0, last_shown: 0, older, newer: None, weight, gone: false});
+	let slot = match self.free.pop() {
+	    Some(slot) => {
+		self.slots[slot] = entry;
+		slot
+	    },
+	    None => {
+		self.slots.push(entry);
+		self.slots.len()-1
+	    },
+	};
+	if let Some(entry) = older.and_then(|older| self.slots[older].as_mut()) {
+	    entry.newer = Some(slot);
 	}
-	self.data.pop_front()
+	self.index.insert(id, slot);
+	self.weights[lane].add(slot, weight);
+	slot
     }
-    pub fn push(&mut self, message: Message) {
-	self.data.push_back(message);
+    fn release(&mut self, slot: usize) -> Option<Arc<Message>> { // out of its slot, which must already be out of its lane
+	let Slot{message, older, newer, weight, gone, ..} = self.slots[slot].take()?;
+	self.free.push(slot);
+	if gone { // already unlinked
+	    self.gone -= 1;
+	    return None;
+	}
+	self.weights[message.priority as usize].add(slot, -weight);
+	if let Some(entry) = older.and_then(|older| self.slots[older].as_mut()) {
+	    entry.newer = newer;
+	}
+	match newer.and_then(|newer| self.slots[newer].as_mut()) {
+	    Some(entry) => entry.older = older,
+	    None => match older { // we were the newest
+		Some(older) => { self.index.insert(message.id.clone(), older); },
+		None => { self.index.remove(&message.id); },
+	    },
+	}
+	Some(message)
     }
-    pub fn push_update(&mut self, message: Message) {
-	// Check through current messages
-	// If there's one with the same ID, update the contents
+    pub fn remove(&mut self, id: &Id) { // every message with this id, through its chain rather than its lane
+	let mut next = self.index.remove(id);
+	while let Some(slot) = next {
+	    let entry = match self.slots[slot].as_mut() {
+		Some(entry) => entry,
+		None => break,
+	    };
+	    next = entry.older.take();
+	    entry.newer = None;
+	    entry.gone = true;
+	    self.weights[entry.message.priority as usize].add(slot, -entry.weight);
+	    entry.weight = 0.0;
+	    self.gone += 1;
+	}
+    }
+    pub fn push<M: Into<Arc<Message>>>(&mut self, message: M) {
+	let message = message.into();
+	let lane = message.priority as usize;
+	let slot = self.store(message);
+	self.enqueue(lane, slot);
+    }
+    pub fn is_empty(&self) -> bool {
+	self.len() == 0
+    }
+    pub fn len(&self) -> usize {
+	self.lanes.iter().map(VecDeque::len).sum::<usize>() - self.gone
+    }
+    pub fn contains(&self, id: &Id) -> bool {
+	self.index.contains_key(id)
+    }
+    pub fn drop_oldest(&mut self) { // next in line of the lowest waiting lane
+	while let Some(slot) = self.lanes.iter_mut().find_map(VecDeque::pop_front) {
+	    if self.release(slot).is_some() { // not one already gone
+		return;
+	    }
+	}
+    }
+    pub fn drop_random(&mut self, rng: &mut dyn RngCore) {
+	let len = self.len();
+	if len == 0 {
+	    return;
+	}
+	let mut i = rng.gen_range(0, len);
+	let slots = &self.slots;
+	let live = |slot: &usize| slots[*slot].as_ref().is_some_and(|entry| !entry.gone);
+	for lane in 0..LANES {
+	    let here = self.lanes[lane].iter().filter(|slot| live(slot)).count();
+	    if i < here {
+		let slot = *self.lanes[lane].iter().filter(|slot| live(slot)).nth(i).expect("counted above");
+		self.unqueue(lane, slot);
+		self.release(slot);
+		return;
+	    }
+	    i -= here;
+	}
+    }
+    pub fn push_update<M: Into<Arc<Message>>>(&mut self, message: M) {
+	// Look up the ID
+	// If there's a message with it, update that in place, most recently pushed first
 	// else, push normally
-	if let Some(compare_msg) = self.data.iter_mut().rev().find(|cmp| cmp.id == message.id) { // starting from the back is recycle-pop safe
-	    compare_msg.contents = message.contents;
-	} else {
-	    self.push(message);
+	let message = message.into();
+	match self.index.get(&message.id) {
+	    Some(&slot) if self.slots[slot].is_some() => self.replace(slot, message),
+	    _ => self.push(message),
 	}
     }
-    pub fn append(&mut self, mut messages: VecDeque<Message>){
-	self.data.append(&mut messages);
+    fn replace(&mut self, slot: usize, message: Arc<Message>) { // a new version of a slot's message, times shown carry over
+	let entry = match self.slots[slot].as_mut() {
+	    Some(entry) => entry,
+	    None => return,
+	};
+	let (from, to) = (entry.message.priority as usize, message.priority as usize);
+	self.weights[from].add(slot, -entry.weight);
+	entry.weight = message.pick_weight();
+	self.weights[to].add(slot, entry.weight);
+	entry.message = message;
+	if from != to { // the only slow case, the old lane has to be searched
+	    self.unqueue(from, slot);
+	    self.enqueue(to, slot);
+	}
+    }
+    pub fn remove_where<F: FnMut(&Message) -> bool>(&mut self, mut predicate: F) {
+	let slots = &self.slots;
+	let mut removed = Vec::new();
+	for lane in &mut self.lanes {
+	    lane.retain(|&slot| match slots[slot].as_ref() {
+		Some(entry) if entry.gone || predicate(&entry.message) => { // clearing out gone slots on the way
+		    removed.push(slot);
+		    false
+		},
+		_ => true,
+	    });
+	}
+	removed.into_iter().for_each(|slot| { self.release(slot); });
     }
-    pub fn append_update(&mut self, messages: VecDeque<Message>){
-	messages.into_iter().for_each(|message| self.push_update(message));
+    // rewrite every message matching predicate in place, returning the new versions
+    pub fn update_where<P: FnMut(&Message) -> bool>(&mut self, mut predicate: P, update: &mut dyn FnMut(&mut Message)) -> Vec<Arc<Message>> {
+	let mut updated = Vec::new();
+	for slot in 0..self.slots.len() {
+	    let message = match self.slots[slot].as_ref() {
+		Some(entry) if !entry.gone && predicate(&entry.message) => Arc::new(entry.message.updated_with(update)),
+		_ => continue,
+	    };
+	    self.replace(slot, message.clone());
+	    updated.push(message);
+	}
+	updated
     }
-    pub fn drain(&mut self) -> VecDeque<Message> {
-	self.data.drain(..).collect::<VecDeque<Message>>()
+    pub fn clear(&mut self) {
+	self.lanes.iter_mut().for_each(|lane| lane.clear());
+	self.weights.iter_mut().for_each(Weights::clear);
+	self.slots.clear();
+	self.free.clear();
+	self.index.clear();
+	self.gone = 0;
     }
 }
 
 pub type ColorString = Vec<ColorChar>;
 
-#[derive(Copy, Clone)]
+// Turns text into one ColorChar per grapheme cluster, so e and a combining accent share a cell
+// The text is kept as written, a cluster of several scalars is carried whole
+// Control characters would break the layout, and become spaces
+fn color_string(string: &str, attr: chtype) -> ColorString {
+    string.graphemes(true).map(|grapheme| ColorChar::from_cluster(grapheme, attr)).collect()
+}
+
+// ColorChar struct
+// One cell of text, a scalar (or raw chtype) with its curses attributes
+// Grapheme clusters of several scalars, such as क + ि or flag sequences, are carried whole and drawn as a string
+#[derive(Clone)]
 pub struct ColorChar {
-    pub data: u32,
-    pub attr: chtype
+    pub data: u32,                  // the cluster's first scalar when there is one
+    pub attr: chtype,
+    cluster:  Option<Arc<str>>,     // the whole cluster, if it's more than data
 }
 
 impl ColorChar {
-    pub fn new(data: u32, attr: chtype) -> Self {
-	Self{data, attr}
+    pub const fn new(data: u32, attr: chtype) -> Self {
+	Self{data, attr, cluster: None}
+    }
+    pub fn from_cluster(cluster: &str, attr: chtype) -> Self { // one grapheme cluster, control characters become a space
+	let mut scalars = cluster.chars();
+	match (scalars.next(), scalars.next()) {
+	    (Some(base), _) if base.is_control() => Self::new(' ' as u32, attr),
+	    (Some(base), None) => Self::new(base as u32, attr),
+	    (Some(base), Some(_)) => Self{data: base as u32, attr, cluster: Some(cluster.into())},
+	    (None, _) => Self::new(' ' as u32, attr),
+	}
+    }
+    pub fn as_char(&self) -> Option<char> { // None for raw chtypes such as ACS_ line drawing, the first scalar of a cluster
+	std::char::from_u32(self.data)
+    }
+    pub fn cluster(&self) -> Option<&str> { // Some only for clusters of several scalars
+	self.cluster.as_deref()
+    }
+    pub fn text(&self) -> Option<String> { // everything drawn in the cell, None for raw chtypes
+	match &self.cluster {
+	    Some(cluster) => Some(cluster.to_string()),
+	    None => self.as_char().map(String::from),
+	}
+    }
+    pub fn with_attr(&self, attr: chtype) -> Self { // same text, other attributes
+	Self{attr, ..self.clone()}
+    }
+    pub fn width(&self) -> i32 { // how many cells this takes up on a row, 1 or 2
+	let width = match &self.cluster {
+	    Some(cluster) => Some(cluster.width()),
+	    None => self.as_char().and_then(|c| c.width()),
+	};
+	match width {
+	    Some(width) if width >= 2 => 2,
+	    _ => 1,
+	}
     }
 }
 
 impl fmt::Debug for ColorChar {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let replacement = std::char::REPLACEMENT_CHARACTER.to_string();
         f.debug_tuple("")
-         .field(&(self.data as u8 as char))
+         .field(&self.text().unwrap_or(replacement))
          .field(&self.attr)
          .finish()
     }
@@ -84,40 +472,79 @@ impl fmt::Debug for ColorChar {
 
 // Message struct
 // Holds basic info about a message
+#[derive(Clone)]
 pub struct Message {
-    pub contents: ColorString,
-    pub id:       String,
+    pub contents:  ColorString,
+    pub id:        Id,
+    pub expires:   Option<Instant>, // dropped from the queue after this
+    pub max_shows: Option<u32>,     // dropped from the queue after being shown this many times
+    pub priority:  Priority,
+    pub weight:    f64,             // how likely RecyclePolicy::WeightedRandom is to pick this
+}
+
+// Priority enum
+// Which lane of the queue a message waits in, higher lanes get more turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
 }
 
 impl Message {
-    pub fn new(contents: ColorString, id: &str) -> Self {
-	Self{contents, id: id.to_string()}
+    pub fn new<I: Into<Id>>(contents: ColorString, id: I) -> Self {
+	Self{contents, id: id.into(), expires: None, max_shows: None, priority: Priority::Normal, weight: 1.0}
     }
-    pub fn new_simple(string: &str, pair: i16, id: &str) -> Self {
-	let mut contents = ColorString::with_capacity(string.len());
-	for i in 0..string.len() {
-	    contents.push(ColorChar::new(string.as_bytes()[i] as u32, COLOR_PAIR(pair.try_into().unwrap())));
-	}
-	Self::new(contents, id)
+    pub fn new_simple<I: Into<Id>>(string: &str, pair: i16, id: I) -> Self {
+	Self::new(color_string(string, COLOR_PAIR(pair.try_into().unwrap())), id)
+    }
+    pub fn new_styled<I: Into<Id>>(string: &str, attr: chtype, id: I) -> Self { // attr as from Palette::style
+	Self::new(color_string(string, attr), id)
     }
-    pub fn new_with_title(title: &str, body: &str, pair: i16, id: &str) -> Self { // creates new from body, title, and id
+    pub fn new_with_title<I: Into<Id>>(title: &str, body: &str, pair: i16, id: I) -> Self { // creates new from body, title, and id
 	let color = COLOR_PAIR(pair.try_into().unwrap());
-	let mut contents = ColorString::with_capacity(title.len()+body.len());
-	for i in 0..title.len() {
-	    contents.push(ColorChar::new(title.as_bytes()[i] as u32, color | A_BOLD));
-	}
-	for i in 0..body.len() {
-	    contents.push(ColorChar::new(body.as_bytes()[i] as u32, color));
-	}
+	let mut contents = color_string(title, color | A_BOLD);
+	contents.append(&mut color_string(body, color));
 	Self::new(contents, id)
     }
+    pub fn with_ttl(self, ttl: Duration) -> Self { // dropped from the queue once ttl has passed
+	self.with_deadline(Instant::now()+ttl)
+    }
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+	self.expires = Some(deadline);
+	self
+    }
+    pub fn with_max_shows(mut self, max_shows: u32) -> Self { // recycled until shown this many times, even in an open queue
+	self.max_shows = Some(max_shows);
+	self
+    }
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+	self.priority = priority;
+	self
+    }
+    pub fn with_weight(mut self, weight: f64) -> Self {
+	self.weight = weight;
+	self
+    }
     pub fn len(&self) -> usize {
 	self.contents.len()
     }
-}
-
-impl Clone for Message {
-    fn clone(&self) -> Message {
-	Message::new(self.contents.clone(), &self.id.clone())
+    pub fn is_empty(&self) -> bool {
+	self.contents.is_empty()
+    }
+    pub(crate) fn updated_with(&self, update: &mut dyn FnMut(&mut Message)) -> Message { // a changed copy, with the same id
+	let mut message = self.clone();
+	update(&mut message);
+	message.id = self.id.clone(); // the queue's index relies on it
+	message
+    }
+    fn pick_weight(&self) -> f64 { // negative or NaN weights are never picked
+	if self.weight > 0.0 {
+	    self.weight
+	} else {
+	    0.0
+	}
     }
 }
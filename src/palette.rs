@@ -0,0 +1,120 @@
+/*
+ * palette.rs
+ *
+ * Holds colours by what they look like rather than by pair number:
+ *   Style
+ *   Palette
+ */
+
+use std::collections::{HashMap, HashSet};
+use pancurses::{chtype, A_BOLD, A_DIM, A_NORMAL, COLOR_PAIR};
+use crate::builder::check_color;
+use crate::error::Error;
+
+const PAIRS: i16 = 256; // pairs 1 to 255 until the backend says otherwise, 0 belongs to curses
+
+// Style struct
+// Foreground and background curses colors, -1 for the terminal's own, plus attributes such as A_BOLD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub fg:    i16,
+    pub bg:    i16,
+    pub attrs: chtype,
+}
+
+impl Style {
+    pub fn new(fg: i16, bg: i16) -> Self {
+	Self{fg, bg, attrs: A_NORMAL}
+    }
+    pub fn attr(mut self, attr: chtype) -> Self { // added to any already set
+	self.attrs |= attr;
+	self
+    }
+    pub fn bold(self) -> Self {
+	self.attr(A_BOLD)
+    }
+    pub fn dim(self) -> Self {
+	self.attr(A_DIM)
+    }
+}
+
+// Palette struct
+// Hands out a colour pair for each foreground and background combination, the same one every time it's asked
+// Styles come back as attributes, ready for ColorChar, Message::new_styled, Gradient and filler_attr
+// Pairs set up by hand with Scene::init_pair should be reserved first, so they're never handed out too
+// A scene's background gets its pair here too, as black on the background colour, once the scene is built
+// Once a scene is built, only the pairs its terminal has are handed out, see Backend::max_pairs
+// A terminal without colours has none, then every style is just its attributes and nothing is set up
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pairs:    HashMap<(i16, i16), i16>, // (fg, bg) to pair
+    reserved: HashSet<i16>,
+    limit:    i16,                      // pairs are below this
+}
+
+impl Palette {
+    pub fn new() -> Self {
+	Self{pairs: HashMap::new(), reserved: HashSet::new(), limit: PAIRS}
+    }
+    pub fn style(&mut self, style: Style) -> Result<chtype, Error> {
+	let pair = self.pair(style.fg, style.bg)?;
+	Ok(COLOR_PAIR(pair as chtype) | style.attrs)
+    }
+    pub fn pair(&mut self, fg: i16, bg: i16) -> Result<i16, Error> {
+	self.allocate(fg, bg).map(|(pair, _)| pair)
+    }
+    pub(crate) fn allocate(&mut self, fg: i16, bg: i16) -> Result<(i16, bool), Error> { // true if the pair is new
+	check_color("foreground", fg)?;
+	check_color("background", bg)?;
+	if self.limit == 0 {
+	    return Ok((0, false)); // pair 0 is whatever the terminal draws with
+	}
+	if let Some(&pair) = self.pairs.get(&(fg, bg)) {
+	    return Ok((pair, false));
+	}
+	let pair = (1..self.limit).find(|pair| !self.reserved.contains(pair)).ok_or(Error::PaletteFull)?;
+	self.reserved.insert(pair);
+	self.pairs.insert((fg, bg), pair);
+	Ok((pair, true))
+    }
+    pub fn reserve(&mut self, pair: i16) { // keep a pair for yourself, it won't be handed out from now on
+	self.reserved.insert(pair);
+    }
+    // a pair being set up by hand, which can't be one handed out already, the background's included
+    // false if there's nothing to set up, as the terminal has no colours
+    pub(crate) fn claim(&mut self, pair: i16) -> Result<bool, Error> {
+	if self.limit == 0 {
+	    return Ok(false);
+	}
+	if pair < 1 || pair >= self.limit {
+	    return Err(Error::InvalidConfig(format!("pair must be in 1..{}, got {}", self.limit, pair)));
+	}
+	if self.pairs.values().any(|&taken| taken == pair) {
+	    return Err(Error::PairTaken(pair));
+	}
+	self.reserve(pair);
+	Ok(true)
+    }
+    pub fn max_pairs(&self) -> i16 { // pairs handed out are below this
+	self.limit
+    }
+    pub(crate) fn limit(&mut self, max_pairs: i16) -> Result<(), Error> { // what the backend has, an error if pairs past it are already out
+	self.limit = max_pairs;
+	if max_pairs == 0 {
+	    return Ok(()); // no colours, so pairs already out are never drawn
+	}
+	if self.pairs.values().any(|&pair| pair >= max_pairs) {
+	    return Err(Error::PaletteFull);
+	}
+	Ok(())
+    }
+    pub fn pairs(&self) -> impl Iterator<Item = (i16, i16, i16)> + '_ { // (pair, fg, bg), for curses init_pair
+	self.pairs.iter().map(|(&(fg, bg), &pair)| (pair, fg, bg))
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+	Self::new()
+    }
+}
@@ -4,113 +4,209 @@
  * Holds the Streak struct and all it's methods
  */
 
-use rand::Rng;
-use pancurses::Window;
-use crate::message::{MessageQueue, ColorString, ColorChar};
+use std::sync::Arc;
+use rand::{Rng, RngCore};
+use crate::id::Id;
+use crate::message::{Message, MessageQueue, ColorChar};
+use crate::backend::Backend;
+use crate::charset::Charset;
+use crate::gradient::Gradient;
+use pancurses::chtype;
 
 // Streak struct
 // Holds a streak's location&length
 // Handles streak movement
 // Can render all characters in a streak
+// Text isn't copied, rows are read straight out of the shared messages
 pub struct Streak {
     head_x: i32, // horizontal coord
     head_y: i32, // Bottom of the streak
     length: i32, // length of streak
-    inner_text: ColorString,    
+    rows:   i32, // rows of text, the screen height when made
+    spans: Vec<Span>,  // which rows came from which message, top to bottom
+    displayed: usize,  // how many spans have been fully drawn
+    filler: Vec<ColorChar>, // one per row, shown outside spans, empty for blanks
+    glitches: Vec<(i32, ColorChar)>, // message rows scrambled for this tick only
 }
 
+// Span struct
+// Rows start..end of a streak hold message, from its character skip on
+// Rows outside every span are padding
+struct Span {
+    message: Arc<Message>,
+    start:   i32,
+    end:     i32,
+    skip:    usize, // the top of the streak can cut a message off
+}
+
+impl Span {
+    fn whole(&self) -> bool { // does this hold every character of its message?
+	self.skip == 0 && (self.end-self.start) as usize == self.message.len()
+    }
+}
+
+const BLANK: ColorChar = ColorChar::new(' ' as u32, 0);
+
 impl Streak {
     // Takes a queue of messages, consuming when needed
-    pub fn new_with_queue(queue: &mut MessageQueue, head_x: i32, length: i32, screen_height: i32, max_padding: i32) -> Self {
-	let mut rng = rand::thread_rng();
-	let mut inner_text = ColorString::with_capacity(screen_height as usize); // prealloc
-	let first_msg_check = queue.pop();
-	if let None = first_msg_check {
-	    for _ in 0..screen_height {
-		inner_text.push(ColorChar{data: ' ' as u32, attr: 0}); // pad out top if required
-	    }
-	    return Streak{head_x, head_y: 0, length, inner_text}; // nothing to do!
+    // Anything that isn't a message is drawn from filler with its attributes, if there is one
+    pub fn new_with_queue(queue: &mut MessageQueue, rng: &mut dyn RngCore, head_x: i32, length: i32, screen_height: i32, max_padding: i32, filler: Option<(&Charset, chtype)>) -> Self {
+	let mut streak = Streak{head_x, head_y: 0, length, rows: screen_height, spans: Vec::new(), displayed: 0, filler: Vec::new(), glitches: Vec::new()};
+	streak.lay_out(queue, rng, max_padding);
+	if let Some((filler, attr)) = filler {
+	    streak.filler = (0..screen_height).map(|_| ColorChar::new(filler.pick(rng) as u32, attr)).collect();
 	}
-	let first_msg = first_msg_check.unwrap();
-	{
-	    let first_string: ColorString = first_msg.contents;
-	    let mut start: i32 = rng.gen_range(0, first_string.len()+max_padding as usize) as i32 - first_string.len() as i32 + 1; // make sure there's at least one char printed, space up to max_padding is allowed at top
-	    if start > screen_height {
-		start = screen_height; // don't overflow
-	    }
-	    if start > 0 {
-		for _ in 0..start {
-		    inner_text.push(ColorChar{data: ' ' as u32, attr: 0}); // pad out top if required
-		}
-	    }
-	    for i in (
-		if start < 0 {
-		    -start // cut off relevant portion of message if required
-		} else {
-		    0
-		}
-	    )..(screen_height.min(first_string.len() as i32)) {
-		inner_text.push(first_string[i as usize]);
-		if inner_text.len() as i32 >= screen_height {
-		    return Streak{head_x, head_y: 0, length, inner_text}; // if first message is too long
-		}
-	    }
+	streak
+    }
+    fn lay_out(&mut self, queue: &mut MessageQueue, rng: &mut dyn RngCore, max_padding: i32) { // pick messages and where they go
+	let screen_height = self.rows;
+	let first_msg = match queue.pop(rng) {
+	    Some(message) => message,
+	    None => return, // nothing to do!
+	};
+	let first_len = first_msg.len() as i32;
+	let mut start: i32 = rng.gen_range(0, (first_msg.len()+max_padding as usize).max(1)) as i32 - first_len + 1; // make sure there's at least one char printed, space up to max_padding is allowed at top
+	if start > screen_height {
+	    start = screen_height; // don't overflow
 	}
-	
-	loop {
-	    let r: i32 = if max_padding > 0 {
+	let skip = (-start).max(0);  // cut off relevant portion of message if required
+	let begin = start.max(0);    // pad out top if required
+	let mut filled = begin + (first_len-skip).min(screen_height-begin);
+	self.spans.push(Span{message: first_msg, start: begin, end: filled, skip: skip as usize});
+
+	while filled < screen_height { // if first message is too long, we're done already
+	    let r: i32 = if max_padding > 1 {
 		rng.gen_range(1,max_padding)
 	    } else {
-		0 // if padding is forced to 0, never pad ever
+		max_padding // if padding is forced to 0, never pad ever
 	    };
-	    if inner_text.len() as i32+r >= screen_height { // terminate early
-		for _ in 0..(screen_height as usize-inner_text.len()) {
-		    inner_text.push(ColorChar{data: ' ' as u32, attr: 0}); // fill remaining
-		}
+	    if filled+r >= screen_height { // terminate early
 		break; // streak is full
-	    } else { // still need more content to fill
-		for _ in 0..r {
-		    inner_text.push(ColorChar{data: ' ' as u32, attr: 0});
-		}
 	    }
+	    filled += r; // still need more content to fill
 
-	    
-	    let next_msg_check = queue.pop();
-	    if let None = next_msg_check {
-		for _ in inner_text.len() as i32..screen_height {
-		    inner_text.push(ColorChar{data: ' ' as u32, attr: 0}); // pad out top if required
+	    let next_msg = match queue.pop(rng) {
+		Some(message) => message,
+		None => break, // nothing to do!
+	    };
+	    let start = filled;
+	    filled = (filled+next_msg.len() as i32).min(screen_height); // print full string, or fill remaining
+	    self.spans.push(Span{message: next_msg, start, end: filled, skip: 0});
+	}
+    }
+    fn span(&self, row: i32) -> Option<&Span> { // the message at a row, if there is one
+	let i = self.spans.partition_point(|span| span.end <= row);
+	self.spans.get(i).filter(|span| span.start <= row)
+    }
+    fn glyph(&self, row: i32) -> ColorChar { // what's at a row, blank between messages
+	self.content(row).map_or(BLANK, |(glyph, _)| glyph)
+    }
+    fn content(&self, row: i32) -> Option<(ColorChar, bool)> { // what's at a row and if it's message text, None for gaps
+	if let Some((_, glitch)) = self.glitches.iter().find(|(glitched, _)| *glitched == row) {
+	    return Some((glitch.clone(), true));
+	}
+	match self.span(row) {
+	    Some(span) => span.message.contents.get(span.skip+(row-span.start) as usize).map(|glyph| (glyph.clone(), true)),
+	    None => self.filler.get(row as usize).map(|glyph| (glyph.clone(), false)),
+	}
+    }
+    pub fn ids(&self) -> impl Iterator<Item = &Id> { // every message this streak carries, top to bottom
+	self.spans.iter().map(|span| &span.message.id)
+    }
+    // messages that have now been drawn in full, each reported once
+    // ones cut off by the top or bottom of the screen never were, so aren't reported
+    pub fn take_displayed(&mut self) -> Vec<Id> {
+	let head_y = self.head_y;
+	let newly = self.spans[self.displayed..].iter().take_while(|span| span.end <= head_y).count();
+	self.displayed += newly;
+	self.spans[self.displayed-newly..self.displayed].iter().filter(|span| span.whole()).map(|span| span.message.id.clone()).collect()
+    }
+    // drop the messages matching predicate, leaving gaps where they were, true if any were
+    pub fn remove_where<F: FnMut(&Message) -> bool>(&mut self, mut predicate: F) -> bool {
+	let (before, shown) = (self.spans.len(), self.displayed);
+	let mut i = 0;
+	let mut unshown = 0; // removed spans that were already reported
+	self.spans.retain(|span| {
+	    let keep = !predicate(&span.message);
+	    if !keep && i < shown {
+		unshown += 1;
+	    }
+	    i += 1;
+	    keep
+	});
+	self.displayed -= unshown;
+	if self.spans.len() == before {
+	    return false;
+	}
+	self.glitches.clear();
+	true
+    }
+    // point rows at the new version of their message, true if any were
+    // shorter text leaves blanks, longer is cut
+    pub fn refresh<F: FnMut(&Message) -> Option<Arc<Message>>>(&mut self, mut updated: F) -> bool {
+	let mut changed = false;
+	for span in &mut self.spans {
+	    if let Some(message) = updated(&span.message) {
+		span.message = message;
+		changed = true;
+	    }
+	}
+	changed
+    }
+    // each visible row re-rolls from charset with chance rate
+    // filler keeps what it rolls, message rows only show it for a tick, and only if messages is set
+    pub fn mutate(&mut self, rng: &mut dyn RngCore, charset: &Charset, rate: f64, messages: bool) {
+	self.glitches.clear();
+	for row in (self.head_y-self.length-1).max(0)..self.head_y.min(self.rows) {
+	    if !rng.gen_bool(rate) {
+		continue;
+	    }
+	    if self.span(row).is_some() {
+		if messages {
+		    let attr = self.glyph(row).attr;
+		    self.glitches.push((row, ColorChar::new(charset.pick(rng) as u32, attr)));
 		}
-		return Streak{head_x, head_y: 0, length, inner_text}; // nothing to do!
+	    } else if let Some(cell) = self.filler.get_mut(row as usize) {
+		cell.data = charset.pick(rng) as u32;
 	    }
-	    let next_msg = next_msg_check.unwrap();
-	    {
-		let next_string: ColorString = next_msg.contents;
-		
-		if inner_text.len()+next_string.len() >= screen_height as usize { // terminate early
-		    for i in 0..(screen_height as usize-inner_text.len()) {
-			inner_text.push(next_string[i as usize]); // fill remaining
-		    }
-		    break; // streak is full
-		} else {
-		    for i in 0..next_string.len() {
-			inner_text.push(next_string[i as usize]); // print full string, move on
-		    }
+	}
+    }
+    // mark rows where a wide glyph of ours also covers the next column over
+    // rows the column to our left covers aren't drawn, so can't cover anything
+    pub fn cover(&self, covered: &mut [bool], screen_width: i32, left: &[bool]) {
+	if self.head_x+2 > screen_width {
+	    return; // drawn narrow, see render
+	}
+	for i in (self.head_y-self.length-1).max(0)..self.head_y.min(self.rows) {
+	    if self.glyph(i).width() == 2 && !is_covered(left, i) {
+		if let Some(row) = covered.get_mut(i as usize) {
+		    *row = true;
 		}
 	    }
 	}
-	Streak{head_x, head_y: 0, length, inner_text}
     }
-    pub fn render(&self, window: &Window) { // print contents to screen
+    // print contents to screen, styled from head to tail if there's a gradient
+    // rows in covered belong to a wide glyph in the column to our left, and are left alone
+    pub fn render(&self, backend: &mut dyn Backend, screen_height: i32, screen_width: i32, gradient: Option<&Gradient>, covered: &[bool]) {
 	for i in (self.head_y-self.length-1)..self.head_y {
-	    if i >= 0 && i < window.get_max_y() {
-		window.attron(self.inner_text[i as usize].attr);
-		window.mvaddch(i,self.head_x,self.inner_text[i as usize].data);
-		window.attroff(self.inner_text[i as usize].attr);
+	    if i >= 0 && i < screen_height && i < self.rows && !is_covered(covered, i) { // screen may have grown since we were made
+		let mut glyph = match (self.content(i), gradient) {
+		    (Some((glyph, message)), Some(gradient)) => gradient.apply(&glyph, self.head_y-1-i, self.length, message),
+		    (Some((glyph, _)), None) => glyph,
+		    (None, _) => BLANK, // gaps stay as they are, so stages can't turn them into blocks
+		};
+		if self.head_x+glyph.width() > screen_width {
+		    glyph = ColorChar::new(' ' as u32, glyph.attr); // wide glyph in the last column has nowhere to go
+		}
+		backend.put(i, self.head_x, glyph);
 	    }
 	}
     }
-    pub fn derender(&self, window: &Window) { // removes first char, makes streak look like it's moving down
-	window.mvaddch(self.head_y-self.length-1, self.head_x, window.getbkgd());
+    pub fn derender(&self, backend: &mut dyn Backend, screen_height: i32, covered: &[bool]) { // removes first char, makes streak look like it's moving down
+	let tail = self.head_y-self.length-1;
+	if tail >= 0 && tail < screen_height && !is_covered(covered, tail) {
+	    backend.clear(tail, self.head_x);
+	}
     }
     pub fn advance(&mut self) {
 	self.head_y+=1;
@@ -122,3 +218,7 @@ impl Streak {
 	self.head_y-self.length+1
     }
 }
+
+fn is_covered(covered: &[bool], row: i32) -> bool {
+    covered.get(row as usize).copied().unwrap_or(false)
+}
@@ -0,0 +1,274 @@
+/*
+ * testing.rs
+ *
+ * Headless scenes for tests and automation:
+ *   VirtualScreen
+ */
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use pancurses::{chtype, Input, COLOR_BLACK};
+use rand::{RngCore, SeedableRng};
+use rand::rngs::StdRng;
+use crate::{ForkedScene, ThreadMsg};
+use crate::backend::Backend;
+use crate::builder::Config;
+use crate::backlog::{Backlog, PushStatus};
+use crate::command::Commands;
+use crate::event::SceneEvent;
+use crate::id::Id;
+use crate::error::Error;
+use crate::keymap::{Action, Keymap};
+use crate::message::{Message, ColorChar, RecyclePolicy};
+use crate::palette::{Palette, Style};
+
+// Grid struct
+// In-memory Backend, one ColorChar per cell
+// A wide glyph takes its cell and the one to its right, which is marked with data 0
+pub(crate) struct Grid {
+    height: i32,
+    width:  i32,
+    cells:  Vec<ColorChar>,
+    input:  VecDeque<Input>, // keys waiting to be polled
+}
+
+impl Grid {
+    fn new(height: i32, width: i32) -> Self {
+	let (height, width) = (height.max(0), width.max(0));
+	Self{height, width, cells: vec![Self::blank(); (height*width) as usize], input: VecDeque::new()}
+    }
+    fn blank() -> ColorChar {
+	ColorChar::new(' ' as u32, 0)
+    }
+    fn index(&self, y: i32, x: i32) -> Option<usize> {
+	if y >= 0 && y < self.height && x >= 0 && x < self.width {
+	    Some((y*self.width + x) as usize)
+	} else {
+	    None
+	}
+    }
+    fn unlink(&mut self, y: i32, x: i32) { // a glyph covering (y, x) is about to be overwritten, remove all of it
+	let i = match self.index(y, x) {
+	    Some(i) => i,
+	    None => return,
+	};
+	if self.cells[i].data == 0 { // right half, the glyph starts one to the left
+	    self.cells[i] = Self::blank();
+	    if let Some(lead) = self.index(y, x-1) {
+		self.cells[lead] = Self::blank();
+	    }
+	} else {
+	    if self.cells[i].width() == 2 {
+		if let Some(right) = self.index(y, x+1) {
+		    self.cells[right] = Self::blank();
+		}
+	    }
+	    self.cells[i] = Self::blank();
+	}
+    }
+}
+
+impl Backend for Grid {
+    fn put(&mut self, y: i32, x: i32, glyph: ColorChar) {
+	let i = match self.index(y, x) {
+	    Some(i) => i,
+	    None => return,
+	};
+	self.unlink(y, x);
+	if glyph.width() == 2 {
+	    match self.index(y, x+1) {
+		Some(right) => {
+		    self.unlink(y, x+1);
+		    self.cells[right] = ColorChar::new(0, glyph.attr);
+		},
+		None => return, // doesn't fit
+	    }
+	}
+	self.cells[i] = glyph;
+    }
+    fn clear(&mut self, y: i32, x: i32) {
+	self.unlink(y, x);
+    }
+    fn size(&self) -> Option<(i32, i32)> {
+	Some((self.height, self.width))
+    }
+    fn flush(&mut self) {
+    }
+    fn poll(&mut self) -> Option<Input> {
+	self.input.pop_front()
+    }
+    fn erase(&mut self) {
+	self.cells.iter_mut().for_each(|cell| *cell = Self::blank());
+    }
+}
+
+// VirtualScreen struct
+// Runs a scene on a Grid in the calling thread, one tick per step()
+// Nothing is drawn to the terminal, so the result can be inspected
+pub struct VirtualScreen {
+    pub(crate) scene: ForkedScene<Grid>,
+    palette: Palette,
+}
+
+impl VirtualScreen {
+    pub fn new(height: i32, width: i32) -> Self { // default settings, see SceneBuilder::build_virtual for the rest
+	Self::spawn(Config::default(), Keymap::default(), Palette::new(), Box::new(StdRng::from_entropy()), height, width)
+    }
+    pub(crate) fn spawn(config: Config, keymap: Keymap, palette: Palette, rng: Box<dyn RngCore + Send>, height: i32, width: i32) -> Self {
+	let backlog = Arc::new(Backlog::new(config.capacity, config.overflow, config.sample_seed));
+	let mut scene = ForkedScene::new(config, keymap, backlog, None, Grid::new(height, width), rng).expect("a grid always has a size");
+	let mut palette = palette;
+	palette.limit(scene.backend.max_pairs()).expect("a grid has as many pairs as a palette starts with");
+	let background = palette.pair(COLOR_BLACK, scene.config.background);
+	for (pair, fg, bg) in palette.pairs() {
+	    scene.handle(ThreadMsg::ColorPair(pair, fg, bg));
+	}
+	if let Ok(pair) = background { // refused for a full palette, which a Scene wouldn't build with
+	    scene.handle(ThreadMsg::Background(pair));
+	}
+	scene.start();
+	Self{scene, palette}
+    }
+    // same as Scene::push, except Overflow::Block turns messages away, as nothing can make room while we wait
+    pub fn push(&mut self, message: Message) -> PushStatus {
+	let status = self.admit();
+	if status != PushStatus::Dropped {
+	    self.scene.handle(ThreadMsg::Push(message));
+	}
+	status
+    }
+    // nothing goes over a channel here, so commands with no value to check never fail
+    pub fn push_update(&mut self, message: Message) {
+	let _ = Commands::push_update(self, message);
+    }
+    pub fn append(&mut self, messages: Vec<Message>) -> Vec<PushStatus> {
+	let statuses: Vec<PushStatus> = messages.iter().map(|_| self.admit()).collect();
+	let admitted = messages.into_iter().zip(&statuses).filter(|(_, status)| **status != PushStatus::Dropped).map(|(message, _)| message).collect();
+	self.scene.handle(ThreadMsg::Append(admitted));
+	statuses
+    }
+    fn admit(&mut self) -> PushStatus {
+	self.scene.backlog.admit().unwrap_or(PushStatus::Dropped)
+    }
+    pub fn append_update(&mut self, messages: Vec<Message>) {
+	let _ = Commands::append_update(self, messages);
+    }
+    pub fn remove<I: Into<Id>>(&mut self, id: I) {
+	let _ = Commands::remove(self, id.into());
+    }
+    pub fn remove_group<I: Into<Id>>(&mut self, prefix: I) {
+	let _ = Commands::remove_group(self, prefix.into());
+    }
+    pub fn remove_where<F: FnMut(&Message) -> bool + Send + 'static>(&mut self, predicate: F) {
+	let _ = Commands::remove_where(self, predicate);
+    }
+    pub fn update_group<I: Into<Id>, F: FnMut(&mut Message) + Send + 'static>(&mut self, prefix: I, update: F) {
+	let _ = Commands::update_group(self, prefix.into(), update);
+    }
+    pub fn clear(&mut self) {
+	let _ = Commands::clear(self);
+    }
+    pub fn set_speed(&mut self, speed: Duration) {
+	let _ = Commands::set_speed(self, speed);
+    }
+    pub fn set_max_padding(&mut self, max_padding: i32) -> Result<(), Error> {
+	Commands::set_max_padding(self, max_padding)
+    }
+    pub fn set_mutation(&mut self, rate: f64) -> Result<(), Error> {
+	Commands::set_mutation(self, rate)
+    }
+    pub fn set_closed(&mut self, closed: bool) -> Result<(), Error> {
+	Commands::set_closed(self, closed)
+    }
+    pub fn set_background(&mut self, background: i16) -> Result<(), Error> {
+	Commands::set_background(self, background)
+    }
+    pub fn set_recycle(&mut self, policy: RecyclePolicy) {
+	let _ = Commands::set_recycle(self, policy);
+    }
+    pub fn bind(&mut self, key: Input, action: Action) {
+	let _ = Commands::bind(self, key, action);
+    }
+    pub fn unbind(&mut self, key: Input) {
+	let _ = Commands::unbind(self, key);
+    }
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+	let _ = Commands::set_keymap(self, keymap);
+    }
+    pub fn init_pair(&mut self, pair: i16, c1: i16, c2: i16) -> Result<(), Error> {
+	Commands::init_pair(self, pair, c1, c2)
+    }
+    pub fn style(&mut self, style: Style) -> Result<chtype, Error> { // same as Scene::style
+	Commands::style(self, style)
+    }
+    pub fn palette(&self) -> &Palette {
+	&self.palette
+    }
+    pub fn events(&mut self) -> Receiver<SceneEvent> { // same events a Scene would send
+	let (tx, rx) = mpsc::channel();
+	self.scene.handle(ThreadMsg::Subscribe(tx));
+	rx
+    }
+    pub fn step(&mut self) { // one tick of the clock, which does nothing while paused
+	if self.scene.ticking() {
+	    self.scene.advance();
+	}
+    }
+    pub fn steps(&mut self, ticks: usize) {
+	for _ in 0..ticks {
+	    self.step();
+	}
+    }
+    pub fn pause(&mut self) {
+	let _ = Commands::pause(self);
+    }
+    pub fn resume(&mut self) {
+	let _ = Commands::resume(self);
+    }
+    pub fn paused(&self) -> bool {
+	self.scene.paused
+    }
+    pub fn single_step(&mut self, ticks: usize) { // same as Scene::step
+	let _ = Commands::step(self, ticks);
+    }
+    pub fn speed(&self) -> Duration { // only changes through keys or set_speed, nothing here waits on it
+	self.scene.config.speed
+    }
+    pub fn press(&mut self, input: Input) -> bool { // as if typed, false if the scene quit
+	self.scene.input(input).unwrap_or(false)
+    }
+    pub fn resize(&mut self, height: i32, width: i32) { // same as the terminal being resized
+	self.scene.backend = Grid::new(height, width);
+	self.scene.resize().expect("a grid always has a size");
+    }
+    pub fn size(&self) -> (i32, i32) { // (height, width)
+	(self.scene.backend.height, self.scene.backend.width)
+    }
+    pub fn cell(&self, y: i32, x: i32) -> Option<ColorChar> {
+	let grid = &self.scene.backend;
+	grid.index(y, x).map(|i| grid.cells[i].clone())
+    }
+    pub fn row(&self, y: i32) -> String { // right halves of wide glyphs are skipped, so the text lines up with the screen
+	(0..self.scene.backend.width).filter_map(|x| self.cell(y, x))
+	    .filter(|cell| cell.data != 0)
+	    .map(|cell| cell.text().unwrap_or_else(|| "?".to_string())).collect()
+    }
+    pub fn dump(&self) -> String { // whole screen as text, one line per row
+	(0..self.scene.backend.height).map(|y| self.row(y)).collect::<Vec<String>>().join("\n")
+    }
+}
+
+impl Commands for VirtualScreen {
+    fn send(&mut self, thread_msg: ThreadMsg) -> Result<(), Error> { // handled right away, so never disconnected
+	self.scene.handle(thread_msg);
+	Ok(())
+    }
+    fn palette_mut(&mut self) -> &mut Palette {
+	&mut self.palette
+    }
+    fn backlog(&self) -> &Backlog {
+	&self.scene.backlog
+    }
+}
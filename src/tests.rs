@@ -0,0 +1,1156 @@
+/*
+ * tests.rs
+ *
+ * Headless tests, driving the scene through a Backend that records what it's given
+ */
+
+use super::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+// TestBackend struct
+// Stands in for a terminal, keeping a Log the test can still read once the backend has moved to the render thread
+// Feeds a fixed list of keys once the scene has drawn its first frame, and can lose its size on resize
+// Flushes and background changes are also sent as Calls, so a test can wait for the render thread to get somewhere
+struct TestBackend {
+    size:         Option<(i32, i32)>,
+    after_resize: Option<(i32, i32)>,
+    keys:         std::collections::VecDeque<Input>,
+    max_pairs:    i16,
+    flushed:      bool,
+    log:          Arc<std::sync::Mutex<Log>>,
+    calls:        Option<mpsc::Sender<Call>>,
+}
+
+#[derive(Default)]
+struct Log {
+    puts:      Vec<(i32, i32, u32)>,
+    clears:    usize,
+    flushes:   usize,
+    pairs:     Vec<(i16, i16, i16)>,
+    shut_down: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    Flush,
+    Background(i16),
+}
+
+impl TestBackend {
+    fn new(size: Option<(i32, i32)>) -> Self {
+	Self{size, after_resize: size, keys: Default::default(), max_pairs: 256, flushed: false, log: Default::default(), calls: None}
+    }
+    fn keys(mut self, keys: Vec<Input>) -> Self {
+	self.keys = keys.into();
+	self
+    }
+    fn after_resize(mut self, size: Option<(i32, i32)>) -> Self {
+	self.after_resize = size;
+	self
+    }
+    fn max_pairs(mut self, max_pairs: i16) -> Self {
+	self.max_pairs = max_pairs;
+	self
+    }
+    fn log(&self) -> Arc<std::sync::Mutex<Log>> {
+	self.log.clone()
+    }
+    fn calls(&mut self) -> Receiver<Call> {
+	let (tx, rx) = mpsc::channel();
+	self.calls = Some(tx);
+	rx
+    }
+    fn call(&self, call: Call) {
+	if let Some(tx) = self.calls.as_ref() {
+	    let _ = tx.send(call);
+	}
+    }
+}
+
+impl Backend for TestBackend {
+    fn put(&mut self, y: i32, x: i32, glyph: ColorChar) {
+	self.log.lock().unwrap().puts.push((y, x, glyph.data));
+    }
+    fn clear(&mut self, _y: i32, _x: i32) {
+	self.log.lock().unwrap().clears += 1;
+    }
+    fn size(&self) -> Option<(i32, i32)> {
+	self.size
+    }
+    fn flush(&mut self) {
+	self.flushed = true;
+	self.log.lock().unwrap().flushes += 1;
+	self.call(Call::Flush);
+    }
+    fn poll(&mut self) -> Option<Input> {
+	if !self.flushed {
+	    return None;
+	}
+	let key = self.keys.pop_front();
+	if key == Some(Input::KeyResize) {
+	    self.size = self.after_resize;
+	}
+	key
+    }
+    fn init_pair(&mut self, pair: i16, fg: i16, bg: i16) {
+	self.log.lock().unwrap().pairs.push((pair, fg, bg));
+    }
+    fn max_pairs(&self) -> i16 {
+	self.max_pairs
+    }
+    fn set_background(&mut self, background: i16) {
+	self.call(Call::Background(background));
+    }
+    fn shutdown(&mut self) {
+	self.log.lock().unwrap().shut_down = true;
+    }
+}
+
+// changes the background, then counts the flushes before the render thread got that far
+fn flushes_until(scene: &mut Scene, calls: &Receiver<Call>, background: i16) -> usize {
+    scene.set_background(background).unwrap();
+    let pair = scene.palette().pairs().find(|&(_, fg, bg)| (fg, bg) == (COLOR_BLACK, background)).unwrap().0;
+    calls.iter().take_while(|call| *call != Call::Background(pair)).filter(|call| *call == Call::Flush).count()
+}
+
+#[test]
+fn scene_draws_through_backend() {
+    let (_tx, rx) = mpsc::channel();
+    let config = Config{max_padding: 0, ..Config::default()};
+    let mut scene = ForkedScene::new(config, Keymap::default(), Arc::new(Backlog::new(None, Overflow::DropOldest, Some(0))), Some(rx), TestBackend::new(Some((10, 4))), Box::new(StdRng::seed_from_u64(0))).unwrap();
+    scene.queue.push(Message::new_simple("abc", 0, "0"));
+    for _ in 0..40 {
+	scene.advance();
+    }
+    let backend = scene.backend.log.lock().unwrap();
+    assert_eq!(backend.flushes, 40);
+    assert!(backend.clears > 0);
+    for c in "abc".chars() {
+	assert!(backend.puts.iter().any(|&(_, _, data)| data == c as u32));
+    }
+    assert!(backend.puts.iter().all(|&(y, x, _)| (0..10).contains(&y) && (0..4).contains(&x)));
+}
+
+#[test]
+fn virtual_screen_starts_blank() {
+    let screen = testing::VirtualScreen::new(3, 5);
+    assert_eq!(screen.size(), (3, 5));
+    assert_eq!(screen.dump(), "     \n     \n     ");
+    assert!(screen.cell(3, 0).is_none());
+}
+
+#[test]
+fn virtual_screen_shows_messages() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(0).build_virtual(8, 3).unwrap();
+    screen.push(Message::new_simple("xyz", 0, "0"));
+    let mut seen = String::new();
+    for _ in 0..60 {
+	screen.step();
+	seen.push_str(&screen.dump());
+    }
+    assert!(seen.contains('x') && seen.contains('y') && seen.contains('z'));
+    assert!(seen.chars().all(|c| "xyz \n".contains(c)));
+}
+
+#[test]
+fn virtual_screen_resize_keeps_streaks() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(0).build_virtual(8, 3).unwrap();
+    screen.push(Message::new_simple("xyz", 0, "0"));
+    screen.steps(10);
+    let before: Vec<String> = (0..3).map(|y| screen.row(y)).collect();
+    screen.resize(4, 6);
+    assert_eq!(screen.size(), (4, 6));
+    for (y, row) in before.iter().enumerate() {
+	assert_eq!(screen.row(y as i32), format!("{}   ", row));
+    }
+    screen.resize(12, 2);
+    screen.steps(10);
+}
+
+#[test]
+fn same_seed_same_frames() {
+    let messages: Vec<Message> = ["alpha", "beta", "gamma"].iter().map(|text| Message::new_simple(text, 0, *text)).collect();
+    let mut a = SceneBuilder::new().max_padding(4).seed(42).build_virtual(12, 10).unwrap();
+    let mut b = SceneBuilder::new().max_padding(4).seed(42).build_virtual(12, 10).unwrap();
+    a.append(messages.clone());
+    b.append(messages);
+    for _ in 0..50 {
+	a.step();
+	b.step();
+	assert_eq!(a.dump(), b.dump());
+    }
+}
+
+#[test]
+fn messages_split_on_graphemes() {
+    let message = Message::new_simple("n\u{e9}日本🙂e\u{301}", 0, "0");
+    let texts: Vec<String> = message.contents.iter().map(|c| c.text().unwrap()).collect();
+    assert_eq!(texts, vec!["n", "\u{e9}", "日", "本", "🙂", "e\u{301}"]); // each as written
+    assert!(message.contents[..5].iter().all(|c| c.cluster().is_none()));
+    assert_eq!(message.contents[5].cluster(), Some("e\u{301}"));
+    assert_eq!(message.contents[5].as_char(), Some('e'));
+    assert_eq!(message.contents.iter().map(|c| c.width()).collect::<Vec<i32>>(), vec![1, 1, 2, 2, 2, 1]);
+    assert_eq!(format!("{:?}", message.contents[2]), "(\"日\", 0)");
+
+    // nothing is normalised away, compatibility characters stay what they are
+    let kept = Message::new_simple("\u{f900}\u{212b}", 0, "0");
+    assert_eq!(kept.contents.iter().map(|c| c.as_char().unwrap()).collect::<String>(), "\u{f900}\u{212b}");
+
+    // clusters of several scalars are kept whole
+    let clusters = Message::new_simple("कि👨\u{200D}👩\u{200D}👧🇯🇵x", 0, "0");
+    let texts: Vec<String> = clusters.contents.iter().map(|c| c.text().unwrap()).collect();
+    assert_eq!(texts, vec!["कि", "👨\u{200D}👩\u{200D}👧", "🇯🇵", "x"]);
+    assert_eq!(clusters.contents[0].cluster(), Some("कि"));
+    assert_eq!(clusters.contents[0].as_char(), Some('क'));
+    assert_eq!(clusters.contents[2].width(), 2);
+    assert_eq!(clusters.contents[0].width(), 2); // the vowel sign takes a column of its own
+    let mut screen = SceneBuilder::new().max_padding(0).seed(0).build_virtual(6, 2).unwrap();
+    screen.push(Message::new_simple("कि", 0, "0"));
+    let mut seen = false;
+    for _ in 0..40 {
+	screen.step();
+	seen |= screen.dump().contains("कि");
+    }
+    assert!(seen);
+    let titled = Message::new_with_title("Ü:", "\tñ", 0, "1");
+    assert_eq!(titled.contents.iter().map(|c| c.as_char().unwrap()).collect::<String>(), "Ü: ñ");
+}
+
+#[test]
+fn wide_glyphs_take_two_cells() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(1).build_virtual(30, 2).unwrap();
+    screen.push(Message::new_simple("日日日", 0, "0"));
+    let mut seen = false;
+    for _ in 0..80 {
+	screen.step();
+	for y in 0..30 {
+	    let (left, right) = (screen.cell(y, 0).unwrap(), screen.cell(y, 1).unwrap());
+	    assert!(right.as_char() != Some('日')); // never starts in the last column
+	    if left.as_char() == Some('日') {
+		assert_eq!(right.data, 0);
+		assert_eq!(screen.row(y), "日");
+		seen = true;
+	    }
+	}
+    }
+    assert!(seen);
+
+    // the column to the right of a wide glyph leaves it alone, even while it has streaks of its own
+    let mut screen = SceneBuilder::new().max_padding(1).filler(Charset::latin()).seed(2).build_virtual(20, 4).unwrap();
+    for i in 0..8 {
+	screen.push(Message::new_simple("日本語日本語", 0, i));
+    }
+    let mut wide = 0;
+    for _ in 0..120 {
+	screen.step();
+	let mut left = Vec::new();
+	for x in 0..3 {
+	    left = screen.scene.columns[x].covered(20, 4, &left);
+	    for y in left.iter().enumerate().filter(|(_, covered)| **covered).map(|(y, _)| y as i32) {
+		let (cell, right) = (screen.cell(y, x as i32).unwrap(), screen.cell(y, x as i32+1).unwrap());
+		assert_eq!((cell.width(), right.data), (2, 0), "row {} column {}:\n{}", y, x, screen.dump());
+		wide += 1;
+	    }
+	}
+    }
+    assert!(wide > 100, "{}", wide);
+}
+
+#[test]
+fn builder_rejects_bad_values() {
+    assert!(SceneBuilder::new().max_padding(-1).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().density(0.0).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().density(1.5).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().streak_length(1.0, 0.5).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().streak_length(-0.1, 0.5).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().capacity(0).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().filler(Charset::default()).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().mutation(1.5).build_virtual(5, 5).is_err());
+    assert!(SceneBuilder::new().mutation(-0.1).build_virtual(5, 5).is_err());
+    match SceneBuilder::new().background(-2).build() {
+	Err(Error::InvalidConfig(_)) => (),
+	_ => panic!("background should have been rejected"),
+    }
+    assert!(SceneBuilder::new().max_padding(1).density(0.5).streak_length(0.5, 0.6).build_virtual(5, 5).is_ok());
+}
+
+#[test]
+fn sparse_density_draws_less() {
+    let drawn = |density: f64| {
+	let mut screen = SceneBuilder::new().density(density).seed(3).build_virtual(20, 40).unwrap();
+	screen.append((0..50).map(|i| Message::new_simple("||||||||", 0, i)).collect());
+	screen.steps(30);
+	screen.dump().matches('|').count()
+    };
+    assert!(drawn(0.05) < drawn(1.0));
+}
+
+#[test]
+fn missing_size_fails_build() {
+    let backend = TestBackend::new(None);
+    let log = backend.log();
+    let result = SceneBuilder::new().build_with_backend(move || backend);
+    assert_eq!(result.err(), Some(Error::ScreenSize));
+    assert!(log.lock().unwrap().shut_down);
+}
+
+#[test]
+fn start_twice_is_an_error() {
+    let backend = TestBackend::new(Some((5, 5)));
+    let log = backend.log();
+    let mut scene = SceneBuilder::new().build_with_backend(move || backend).unwrap();
+    assert_eq!(scene.start(), Ok(()));
+    assert_eq!(scene.start(), Err(Error::AlreadyStarted));
+    assert_eq!(scene.kill(), Ok(()));
+    assert_eq!(scene.join(), Ok(()));
+    assert!(log.lock().unwrap().shut_down);
+}
+
+#[test]
+fn unknown_keys_are_ignored() {
+    let backend = TestBackend::new(Some((5, 5))).keys(vec![Input::KeyF1, Input::KeyUp, Input::Character('x'), Input::Character('q')]);
+    let log = backend.log();
+    let mut scene = SceneBuilder::new().build_with_backend(move || backend).unwrap();
+    scene.start().unwrap();
+    assert_eq!(scene.join(), Ok(()));
+    assert!(!scene.alive());
+    assert!(log.lock().unwrap().shut_down);
+}
+
+#[test]
+fn lost_size_on_resize_is_reported() {
+    let backend = TestBackend::new(Some((5, 5))).keys(vec![Input::KeyResize]).after_resize(None);
+    let log = backend.log();
+    let mut scene = SceneBuilder::new().build_with_backend(move || backend).unwrap();
+    scene.start().unwrap();
+    assert_eq!(scene.join(), Err(Error::ScreenSize));
+    assert!(log.lock().unwrap().shut_down);
+    assert!(!scene.alive());
+}
+
+#[test]
+fn events_follow_messages() {
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(5).build_virtual(10, 4).unwrap();
+    let events = screen.events();
+    screen.push(Message::new_simple("a", 0, "first"));
+    screen.push(Message::new_simple("c", 0, "second"));
+    screen.steps(30);
+    let events: Vec<SceneEvent> = events.try_iter().collect();
+    for id in &["first", "second"] {
+	let spawned = events.iter().position(|event| matches!(event, SceneEvent::StreakSpawned{id: spawned, ..} if spawned == id)).unwrap();
+	let shown = events.iter().position(|event| *event == SceneEvent::MessageDisplayed(Id::from(*id))).unwrap();
+	assert!(spawned < shown);
+    }
+    assert_eq!(events.iter().filter(|event| **event == SceneEvent::QueueEmpty).count(), 1);
+    assert_eq!(events.iter().filter(|event| matches!(event, SceneEvent::MessageDisplayed(_))).count(), 2);
+}
+
+#[test]
+fn cut_off_messages_are_never_displayed() {
+    // twenty characters can't fit on five rows, so however they land they're cut
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(5).build_virtual(5, 1).unwrap();
+    let events = screen.events();
+    screen.push(Message::new_simple("ABCDEFGHIJKLMNOPQRST", 0, "long"));
+    screen.steps(40);
+    let events: Vec<SceneEvent> = events.try_iter().collect();
+    assert!(events.contains(&SceneEvent::StreakSpawned{id: Id::from("long"), column: 0}));
+    assert!(!events.contains(&SceneEvent::MessageDisplayed(Id::from("long"))), "{:?}", events);
+}
+
+#[test]
+fn events_report_resize() {
+    let mut screen = testing::VirtualScreen::new(10, 4);
+    let events = screen.events();
+    screen.resize(6, 7);
+    assert_eq!(events.try_recv(), Ok(SceneEvent::Resize{height: 6, width: 7}));
+}
+
+#[test]
+fn events_report_keys_and_exit() {
+    let keys = vec![Input::KeyF1, Input::Character('q')];
+    let mut scene = SceneBuilder::new().build_with_backend(move || TestBackend::new(Some((5, 5))).keys(keys)).unwrap();
+    let events = scene.events();
+    scene.start().unwrap();
+    scene.join().unwrap();
+    let events: Vec<SceneEvent> = events.iter().collect(); // ends when the thread drops its sender
+    assert!(events.contains(&SceneEvent::Key(Input::KeyF1)));
+    assert_eq!(events.last(), Some(&SceneEvent::Exit(ExitReason::Quit)));
+
+    let mut scene = SceneBuilder::new().build_with_backend(|| TestBackend::new(Some((5, 5)))).unwrap();
+    let events = scene.events();
+    scene.kill().unwrap();
+    assert_eq!(events.iter().last(), Some(SceneEvent::Exit(ExitReason::Killed)));
+}
+
+#[test]
+fn render_thread_sleeps_between_ticks() {
+    let (tx, rx) = mpsc::channel();
+    let config = Config{speed: Duration::from_secs(3600), ..Config::default()};
+    let mut scene = ForkedScene::new(config, Keymap::default(), Arc::new(Backlog::new(None, Overflow::DropOldest, Some(0))), Some(rx), TestBackend::new(Some((5, 5))), Box::new(StdRng::seed_from_u64(0))).unwrap();
+    assert_eq!(scene.timeout(), INPUT_POLL); // not started, so only keys wake it
+    scene.start();
+    assert_eq!(scene.timeout(), INPUT_POLL); // next tick is an hour away
+    let flushes = scene.backend.log.lock().unwrap().flushes;
+    for _ in 0..100 {
+	assert!(scene.update());
+    }
+    assert_eq!(scene.backend.log.lock().unwrap().flushes, flushes); // nothing to do, nothing drawn
+
+    scene.last_updated -= scene.config.speed; // a tick is due
+    assert_eq!(scene.timeout(), Duration::from_secs(0));
+    assert!(scene.update());
+    assert!(scene.update());
+    assert_eq!(scene.backend.log.lock().unwrap().flushes, flushes+1);
+
+    for id in 0..3 { // everything waiting is handled at once
+	tx.send(ThreadMsg::Push(Message::new_simple("msg", 0, id))).unwrap();
+    }
+    assert!(scene.update());
+    assert_eq!(scene.queue.len(), 3);
+    tx.send(ThreadMsg::Pause).unwrap(); // and wait() wakes up for a message
+    assert!(scene.wait());
+    assert!(scene.paused);
+    drop(tx);
+    assert!(!scene.wait());
+}
+
+#[test]
+fn reconfigure_virtual_screen() {
+    let mut screen = SceneBuilder::new().seed(9).build_virtual(6, 6).unwrap();
+    assert!(screen.set_max_padding(-1).is_err());
+    screen.set_max_padding(0).unwrap();
+    let events = screen.events();
+    screen.push(Message::new_simple("loop", 0, "0"));
+    screen.steps(40); // closed, so it keeps coming back
+    assert!(!events.try_iter().any(|event| event == SceneEvent::QueueEmpty));
+    screen.set_closed(false).unwrap();
+    screen.steps(40);
+    assert!(events.try_iter().any(|event| event == SceneEvent::QueueEmpty));
+    screen.set_speed(Duration::from_millis(7));
+    assert_eq!(screen.speed(), Duration::from_millis(7));
+    assert!(matches!(screen.set_background(-5), Err(Error::InvalidConfig(_))));
+    screen.set_background(COLOR_BLUE).unwrap();
+    screen.bind(Input::Character('x'), Action::Pause);
+    assert!(screen.press(Input::Character('x')));
+    assert!(screen.paused());
+    screen.unbind(Input::Character('x'));
+    assert!(screen.press(Input::Character('x')));
+    assert!(screen.paused());
+    screen.set_keymap(Keymap::new());
+    assert!(screen.press(Input::Character('q'))); // nothing bound any more
+    assert_eq!(screen.init_pair(1, COLOR_RED, COLOR_BLACK), Err(Error::PairTaken(1))); // the first background
+    assert_eq!(screen.init_pair(2, COLOR_RED, COLOR_BLACK), Err(Error::PairTaken(2))); // and the blue one
+    screen.init_pair(5, COLOR_RED, COLOR_BLACK).unwrap();
+    let style = screen.style(Style::new(COLOR_GREEN, COLOR_BLACK)).unwrap();
+    assert_ne!(style & A_COLOR, COLOR_PAIR(5));
+}
+
+#[test]
+fn reconfigure_running_scene() {
+    let mut backend = TestBackend::new(Some((5, 5)));
+    let calls = backend.calls();
+    let mut scene = SceneBuilder::new().speed(Duration::from_secs(3600)).build_with_backend(move || backend).unwrap();
+    scene.start().unwrap();
+    assert_eq!(flushes_until(&mut scene, &calls, COLOR_RED), 1); // only the first frame, the next tick is an hour away
+    scene.set_speed(Duration::from_millis(1)).unwrap();
+    assert!(calls.iter().take(3).all(|call| call == Call::Flush)); // ticking again without anything else sent
+    assert!(matches!(scene.set_background(-5), Err(Error::InvalidConfig(_))));
+    assert!(matches!(scene.set_max_padding(-1), Err(Error::InvalidConfig(_))));
+    scene.set_background(COLOR_BLUE).unwrap();
+    scene.set_max_padding(3).unwrap();
+    scene.set_closed(false).unwrap();
+    scene.kill().unwrap();
+    assert_eq!(scene.set_speed(Duration::from_millis(5)), Err(Error::Disconnected));
+}
+
+#[test]
+fn paused_scene_holds_its_frame() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(4).build_virtual(10, 6).unwrap();
+    screen.push(Message::new_simple("frozen", 0, "0"));
+    screen.steps(8);
+    assert!(screen.press(Input::Character('p')));
+    assert!(screen.paused());
+    let frame = screen.dump();
+    screen.push(Message::new_simple("later", 0, "1"));
+    screen.steps(20);
+    assert_eq!(screen.dump(), frame);
+    screen.resize(10, 8); // a resize redraws what was there
+    assert_eq!((0..10).map(|y| screen.row(y)[..6].to_string()).collect::<Vec<String>>().join("\n"), frame);
+    screen.resize(10, 6);
+    assert!(screen.press(Input::Character(' '))); // single step moves things
+    assert_ne!(screen.dump(), frame);
+    assert!(screen.paused());
+    let stepped = screen.dump();
+    screen.steps(5);
+    assert_eq!(screen.dump(), stepped);
+    screen.resume();
+    screen.steps(5);
+    assert_ne!(screen.dump(), stepped);
+    screen.pause();
+    screen.single_step(3);
+    assert!(screen.paused());
+    assert!(!screen.press(Input::Character('q')));
+}
+
+#[test]
+fn running_scene_pauses_and_steps() {
+    let mut backend = TestBackend::new(Some((5, 5)));
+    let calls = backend.calls();
+    let mut scene = SceneBuilder::new().speed(Duration::from_secs(3600)).build_with_backend(move || backend).unwrap();
+    scene.start().unwrap();
+    scene.pause().unwrap();
+    scene.set_speed(Duration::from_millis(1)).unwrap(); // would tick right away if it weren't paused
+    assert_eq!(flushes_until(&mut scene, &calls, COLOR_RED), 1);
+    scene.step(3).unwrap();
+    assert_eq!(flushes_until(&mut scene, &calls, COLOR_GREEN), 3);
+    scene.resume().unwrap();
+    assert_eq!(calls.recv(), Ok(Call::Flush));
+    scene.kill().unwrap();
+}
+
+#[test]
+fn keymap_rebinds_keys() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let keymap = Keymap::default()
+	.unbind(Input::Character('q'))
+	.bind(Input::Character('+'), Action::Faster)
+	.bind(Input::Character('-'), Action::Slower)
+	.bind(Input::Character('c'), Action::Clear)
+	.on(Input::KeyF5, move |key| log.lock().unwrap().push(key));
+    let mut screen = SceneBuilder::new().keymap(keymap).speed(Duration::from_millis(100)).seed(2).build_virtual(8, 8).unwrap();
+    let events = screen.events();
+    screen.push(Message::new_simple("kiosk", 0, "0"));
+    screen.steps(10);
+
+    assert!(screen.press(Input::Character('q'))); // no longer quits, so it's forwarded
+    assert_eq!(events.try_iter().filter(|event| *event == SceneEvent::Key(Input::Character('q'))).count(), 1);
+    assert!(screen.press(Input::KeyF5));
+    assert_eq!(*seen.lock().unwrap(), vec![Input::KeyF5]);
+    assert!(!events.try_iter().any(|event| matches!(event, SceneEvent::Key(_))));
+
+    screen.press(Input::Character('+'));
+    assert_eq!(screen.speed(), Duration::from_millis(80));
+    screen.press(Input::Character('-'));
+    screen.press(Input::Character('-'));
+    assert_eq!(screen.speed(), Duration::from_millis(125));
+
+    screen.press(Input::Character('c')); // same as Scene::clear, so it doesn't come back either
+    assert!(screen.dump().chars().all(|c| c == ' ' || c == '\n'));
+    screen.steps(20);
+    assert!(screen.dump().chars().all(|c| c == ' ' || c == '\n'));
+    assert!(!screen.press(Input::KeyDC));
+}
+
+#[test]
+fn keymap_can_swallow_unbound_keys() {
+    let mut screen = SceneBuilder::new().keymap(Keymap::new().forward_unbound(false)).build_virtual(4, 4).unwrap();
+    let events = screen.events();
+    assert!(screen.press(Input::Character('q')));
+    assert!(screen.press(Input::Character('x')));
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn running_scene_rebinds_keys() {
+    let keys = vec![Input::Character('q'), Input::Character('x')];
+    let mut scene = SceneBuilder::new().build_with_backend(move || TestBackend::new(Some((5, 5))).keys(keys)).unwrap();
+    let events = scene.events();
+    scene.unbind(Input::Character('q')).unwrap();
+    scene.bind(Input::Character('x'), Action::Quit).unwrap();
+    scene.start().unwrap();
+    scene.join().unwrap();
+    let events: Vec<SceneEvent> = events.iter().filter(|event| matches!(event, SceneEvent::Key(_) | SceneEvent::Exit(_))).collect();
+    assert_eq!(events, vec![SceneEvent::Key(Input::Character('q')), SceneEvent::Exit(ExitReason::Quit)]);
+}
+
+#[test]
+fn removed_messages_stop_recycling() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(8).build_virtual(6, 10).unwrap();
+    let events = screen.events();
+    screen.append(vec![
+	Message::new_simple("AAA", 0, "alert"),
+	Message::new_simple("BBB", 0, "alert"),
+	Message::new_simple("ccc", 0, "metric"),
+	Message::new_simple("ddd", 0, "other"),
+    ]);
+    screen.steps(20);
+    screen.remove("alert");
+    screen.remove_where(|message| message.contents[0].data == 'd' as u32);
+    screen.steps(20); // let anything already falling finish
+    events.try_iter().count();
+    screen.steps(40);
+    let spawned: Vec<Id> = events.try_iter().filter_map(|event| match event {
+	SceneEvent::StreakSpawned{id, ..} => Some(id),
+	_ => None,
+    }).collect();
+    assert!(!spawned.is_empty());
+    assert!(spawned.iter().all(|id| id == "metric"));
+    screen.clear();
+    screen.steps(20);
+    assert!(events.try_iter().any(|event| event == SceneEvent::QueueEmpty));
+}
+
+#[test]
+fn messages_expire_and_run_out() {
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(9).build_virtual(6, 10).unwrap();
+    let events = screen.events();
+    screen.append(vec![
+	Message::new_simple("old", 0, "stale").with_deadline(Instant::now()),
+	Message::new_simple("thrice", 0, "limited").with_max_shows(3),
+	Message::new_simple("later", 0, "fresh").with_ttl(Duration::from_secs(60)),
+    ]);
+    screen.steps(60);
+    let spawned: Vec<Id> = events.try_iter().filter_map(|event| match event {
+	SceneEvent::StreakSpawned{id, ..} => Some(id),
+	_ => None,
+    }).collect();
+    assert!(!spawned.iter().any(|id| id == "stale"));
+    assert_eq!(spawned.iter().filter(|id| *id == "limited").count(), 3); // recycled in an open queue until used up
+    assert_eq!(spawned.iter().filter(|id| *id == "fresh").count(), 1);
+}
+
+#[test]
+fn critical_messages_jump_the_queue() {
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(10).build_virtual(6, 4).unwrap();
+    let events = screen.events();
+    screen.append((0..500).map(|i| Message::new_simple("log line", 0, i)).collect());
+    screen.steps(10);
+    screen.push(Message::new_simple("ALERT", 0, "alert").with_priority(Priority::Critical));
+    events.try_iter().count();
+    screen.steps(20); // a second at the default speed
+    assert!(events.try_iter().any(|event| matches!(event, SceneEvent::StreakSpawned{ref id, ..} if id == "alert")));
+}
+
+#[test]
+fn low_priority_still_gets_turns() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(11).build_virtual(6, 10).unwrap();
+    let events = screen.events();
+    screen.append(vec![
+	Message::new_simple("ALERT", 0, "alert").with_priority(Priority::Critical),
+	Message::new_simple("note", 0, "note").with_priority(Priority::Low),
+    ]);
+    screen.steps(40);
+    let spawned: Vec<Id> = events.try_iter().filter_map(|event| match event {
+	SceneEvent::StreakSpawned{id, ..} => Some(id),
+	_ => None,
+    }).collect();
+    let alerts = spawned.iter().filter(|id| *id == "alert").count();
+    let notes = spawned.iter().filter(|id| *id == "note").count();
+    assert!(notes > 0);
+    assert!(alerts > notes);
+}
+
+fn pop_ids(queue: &mut MessageQueue, rng: &mut StdRng, pops: usize) -> Vec<Id> {
+    (0..pops).filter_map(|_| queue.pop(rng)).map(|message| message.id.clone()).collect()
+}
+
+#[test]
+fn recycle_policies_pick_in_their_own_order() {
+    let mut rng = StdRng::seed_from_u64(12);
+    let abc = || vec![Message::new_simple("a", 0, "a"), Message::new_simple("b", 0, "b"), Message::new_simple("c", 0, "c")];
+
+    let mut queue = MessageQueue::new(3, true, RecyclePolicy::RoundRobin);
+    abc().into_iter().for_each(|message| queue.push(message));
+    assert_eq!(pop_ids(&mut queue, &mut rng, 6), vec!["a", "b", "c", "a", "b", "c"]);
+
+    queue = MessageQueue::new(3, true, RecyclePolicy::Shuffled);
+    abc().into_iter().for_each(|message| queue.push(message));
+    let shuffled = pop_ids(&mut queue, &mut rng, 300);
+    assert!(shuffled.windows(2).all(|pair| pair[0] != pair[1]));
+    assert!(["a", "b", "c"].iter().all(|id| shuffled.iter().filter(|shown| shown == id).count() > 50));
+    assert!(shuffled.chunks(3).any(|round| round != ["a", "b", "c"]));
+
+    queue = MessageQueue::new(3, true, RecyclePolicy::WeightedRandom);
+    queue.push(Message::new_simple("a", 0, "heavy").with_weight(9.0));
+    queue.push(Message::new_simple("b", 0, "light"));
+    queue.push(Message::new_simple("c", 0, "never").with_weight(0.0));
+    let weighted = pop_ids(&mut queue, &mut rng, 1000);
+    assert!(weighted.iter().filter(|id| *id == "heavy").count() > 800);
+    assert!(weighted.iter().any(|id| id == "light"));
+    assert!(!weighted.iter().any(|id| id == "never"));
+
+    queue = MessageQueue::new(3, true, RecyclePolicy::LeastRecentlyShown);
+    abc().into_iter().for_each(|message| queue.push(message));
+    assert_eq!(pop_ids(&mut queue, &mut rng, 2), vec!["a", "b"]);
+    queue.push(Message::new_simple("d", 0, "d"));
+    assert_eq!(pop_ids(&mut queue, &mut rng, 5), vec!["c", "d", "a", "b", "c"]);
+}
+
+#[test]
+fn recycle_policies_keep_up_with_changes() {
+    let mut rng = StdRng::seed_from_u64(13);
+
+    // switching sorts by what was shown under the old policy
+    let mut queue = MessageQueue::new(3, true, RecyclePolicy::RoundRobin);
+    ["a", "b", "c"].iter().for_each(|id| queue.push(Message::new_simple(id, 0, *id)));
+    assert_eq!(pop_ids(&mut queue, &mut rng, 2), vec!["a", "b"]);
+    queue.set_policy(RecyclePolicy::LeastRecentlyShown);
+    assert_eq!(pop_ids(&mut queue, &mut rng, 3), vec!["c", "a", "b"]);
+    queue.set_closed(false); // and leaving it behind is just as fine
+    queue.push(Message::new_simple("d", 0, "d"));
+    queue.set_closed(true);
+    assert_eq!(pop_ids(&mut queue, &mut rng, 2), vec!["d", "c"]);
+
+    // weights follow pushes, updates and removals in big queues
+    let mut queue = MessageQueue::new(5000, true, RecyclePolicy::WeightedRandom);
+    for i in 0..5000 {
+	queue.push(Message::new_simple("0", 0, i).with_weight(0.0));
+    }
+    queue.push(Message::new_simple("1", 0, "only").with_weight(1.0));
+    assert!(pop_ids(&mut queue, &mut rng, 50).iter().all(|id| *id == "only"));
+    queue.remove_where(|message| message.id == "only");
+    queue.push_update(Message::new_simple("2", 0, 1234).with_weight(2.0));
+    assert!(pop_ids(&mut queue, &mut rng, 50).iter().all(|id| *id == Id::from(1234)));
+    queue.push_update(Message::new_simple("2", 0, 1234).with_weight(0.0));
+    queue.push(Message::new_simple("3", 0, "new").with_weight(1.0)); // in the slot "only" left
+    assert!(pop_ids(&mut queue, &mut rng, 50).iter().all(|id| *id == "new"));
+}
+
+#[test]
+fn updates_find_messages_after_recycling() {
+    let mut rng = StdRng::seed_from_u64(13);
+    let mut queue = MessageQueue::new(0, true, RecyclePolicy::RoundRobin);
+    (0..1000).for_each(|i| queue.push(Message::new_simple("old", 0, i)));
+    pop_ids(&mut queue, &mut rng, 1500); // every message has moved at least once
+    (0..1000).for_each(|i| queue.push_update(Message::new_simple("new", 0, i)));
+    queue.push_update(Message::new_simple("top", 0, "7").with_priority(Priority::High));
+    let popped: Vec<Arc<Message>> = (0..1000).filter_map(|_| queue.pop(&mut rng)).collect();
+    assert_eq!(popped[0].id, "7");
+    assert_eq!(popped[0].contents[0].data, 't' as u32);
+    assert!(popped.iter().all(|message| message.contents[0].data == 'n' as u32 || message.id == "7"));
+    assert!(popped.iter().filter(|message| message.id == "7").count() > 600); // moved up to the high lane, which gets 4 turns in 6
+
+    queue.push_update(Message::new_simple("again", 0, "500"));
+    queue.remove_where(|message| message.id != "500");
+    assert_eq!(queue.pop(&mut rng).map(|message| message.contents[0].data), Some('a' as u32));
+}
+
+#[test]
+fn shared_ids_leave_from_anywhere() {
+    let text = |message: Arc<Message>| message.contents.iter().map(|c| c.as_char().unwrap()).collect::<String>();
+    let mut rng = StdRng::seed_from_u64(26);
+    let mut queue = MessageQueue::new(4, false, RecyclePolicy::RoundRobin);
+    for body in ["1", "2", "3"].iter() {
+	queue.push(Message::new_simple(body, 0, "same"));
+    }
+    queue.push(Message::new_simple("x", 0, "other"));
+    assert_eq!(queue.pop(&mut rng).map(text), Some("1".to_string()));
+    queue.push_update(Message::new_simple("9", 0, "same")); // the newest one
+    queue.remove_where(|message| message.contents[0].as_char() == Some('9'));
+    queue.push_update(Message::new_simple("8", 0, "same")); // which leaves the one before it
+    assert_eq!((0..3).filter_map(|_| queue.pop(&mut rng)).map(text).collect::<Vec<String>>(), vec!["8", "x"]);
+    assert!(!queue.contains(&Id::from("same")) && queue.is_empty());
+
+    // one id for everything, as piped input often has, drains just the same
+    for i in 0..10_000 {
+	queue.push(Message::new_simple(&i.to_string(), 0, ""));
+    }
+    assert_eq!((0..10_000).filter_map(|_| queue.pop(&mut rng)).count(), 10_000);
+    assert!(!queue.contains(&Id::new()));
+
+    // removing by id takes every copy, and whatever comes after with the id is kept
+    for i in 0..1000 {
+	queue.push(Message::new_simple("a", 0, if i % 2 == 0 {"even"} else {"odd"}));
+    }
+    queue.remove(&Id::from("even"));
+    assert_eq!(queue.len(), 500);
+    assert!(!queue.contains(&Id::from("even")));
+    queue.push(Message::new_simple("b", 0, "even"));
+    queue.drop_oldest();
+    queue.drop_random(&mut rng);
+    assert_eq!(queue.len(), 499);
+    let left = pop_ids(&mut queue, &mut rng, 1000);
+    assert_eq!(left.len(), 499);
+    assert_eq!(left.iter().filter(|id| **id == "even").count(), 1);
+    assert!(queue.is_empty());
+
+    let mut queue = MessageQueue::new(4, true, RecyclePolicy::WeightedRandom);
+    queue.push(Message::new_simple("a", 0, "heavy").with_weight(100.0));
+    queue.push(Message::new_simple("b", 0, "light"));
+    queue.remove(&Id::from("heavy"));
+    assert!(pop_ids(&mut queue, &mut rng, 20).iter().all(|id| *id == "light"));
+}
+
+#[test]
+fn updates_reach_falling_text() {
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(14).build_virtual(8, 3).unwrap();
+    screen.push(Message::new_simple("1111111111", 0, "counter"));
+    screen.steps(4);
+    screen.pause();
+    let ones = screen.dump().matches('1').count();
+    assert!(ones > 0);
+    screen.push_update(Message::new_simple("2222222222", 0, "counter"));
+    assert_eq!(screen.dump().matches('1').count(), 0); // redrawn without a tick
+    assert_eq!(screen.dump().matches('2').count(), ones);
+    screen.append_update(vec![Message::new_simple("3", 0, "counter")]);
+    assert!(screen.dump().matches('3').count() <= 1);
+    assert_eq!(screen.dump().matches('2').count(), 0);
+}
+
+#[test]
+fn recycled_messages_share_their_text() {
+    let mut rng = StdRng::seed_from_u64(15);
+    let mut queue = MessageQueue::new(1, true, RecyclePolicy::RoundRobin);
+    queue.push(Message::new_simple("shared", 0, "0"));
+    let first = queue.pop(&mut rng).unwrap();
+    let second = queue.pop(&mut rng).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    queue.push_update(Message::new_simple("fresh", 0, "0"));
+    assert!(!Arc::ptr_eq(&first, &queue.pop(&mut rng).unwrap()));
+}
+
+#[test]
+fn ids_are_paths() {
+    assert_eq!(Id::from("host1/cpu"), Id::from(("host1", "cpu")));
+    assert_eq!(Id::from(("host1", 3)), "host1/3");
+    assert_eq!(Id::from(7), Id::from("7"));
+    assert_eq!(Id::from(""), Id::new());
+    assert!(Id::from("host1/cpu").starts_with(&Id::from("host1")));
+    assert!(!Id::from("host10/cpu").starts_with(&Id::from("host1")));
+    assert!(Id::from("anything").starts_with(&Id::new()));
+    assert_eq!(Id::new().child("a").child(1).to_string(), "a/1");
+
+    // slashes split wherever they come from, so printing and parsing agree
+    let nested = Id::from(("a/b", "c"));
+    assert_eq!(nested, Id::from("a/b/c"));
+    assert_eq!(Id::from(nested.to_string().as_str()), nested);
+    assert!(nested.starts_with(&Id::from("a/b")));
+    assert_eq!(Id::from("a").child("b/c").segments().count(), 3);
+    assert_eq!(Id::from("a").child(""), Id::from("a"));
+}
+
+#[test]
+fn groups_update_and_remove_together() {
+    let mut screen = SceneBuilder::new().max_padding(0).seed(16).build_virtual(8, 6).unwrap();
+    let events = screen.events();
+    screen.append(vec![
+	Message::new_simple("aaaa", 0, ("host1", "cpu")),
+	Message::new_simple("aaaa", 0, ("host1", "mem")),
+	Message::new_simple("bbbb", 0, ("host10", "cpu")),
+    ]);
+    screen.steps(10);
+    screen.pause();
+    screen.update_group("host1", |message| message.contents.iter_mut().for_each(|glyph| glyph.data = 'z' as u32));
+    assert_eq!(screen.dump().matches('a').count(), 0); // falling text changed too
+    assert!(screen.dump().contains('z'));
+    screen.resume();
+    screen.remove_group("host1");
+    screen.steps(20);
+    events.try_iter().count();
+    screen.steps(30);
+    let spawned: Vec<Id> = events.try_iter().filter_map(|event| match event {
+	SceneEvent::StreakSpawned{id, ..} => Some(id),
+	_ => None,
+    }).collect();
+    assert!(!spawned.is_empty());
+    assert!(spawned.iter().all(|id| *id == "host10/cpu"));
+}
+
+#[test]
+fn full_queues_overflow_by_policy() {
+    let spawned = |screen: &mut testing::VirtualScreen| {
+	let events = screen.events();
+	screen.steps(60);
+	events.try_iter().filter_map(|event| match event {
+	    SceneEvent::StreakSpawned{id, ..} => Some(id),
+	    _ => None,
+	}).collect::<Vec<Id>>()
+    };
+    let five = || (0..5).map(|i| Message::new_simple("msg", 0, i)).collect::<Vec<Message>>();
+
+    let mut screen = SceneBuilder::new().closed(false).capacity(3).overflow(Overflow::DropNewest).seed(17).build_virtual(6, 6).unwrap();
+    assert_eq!(screen.append(five()), vec![PushStatus::Queued, PushStatus::Queued, PushStatus::Queued, PushStatus::Dropped, PushStatus::Dropped]);
+    assert_eq!(spawned(&mut screen), vec!["0", "1", "2"]);
+
+    let mut screen = SceneBuilder::new().closed(false).capacity(3).overflow(Overflow::DropOldest).seed(17).build_virtual(6, 6).unwrap();
+    assert_eq!(screen.append(five())[3..], [PushStatus::Replaced, PushStatus::Replaced]);
+    assert_eq!(spawned(&mut screen), vec!["2", "3", "4"]);
+
+    assert!(SceneBuilder::new().capacity(3).overflow(Overflow::Block).build_virtual(6, 6).is_err()); // closed would block for good
+    assert!(SceneBuilder::new().overflow(Overflow::Block).build_virtual(6, 6).is_ok()); // never full
+    let mut screen = SceneBuilder::new().closed(false).capacity(3).overflow(Overflow::Block).seed(17).build_virtual(6, 6).unwrap();
+    assert!(matches!(screen.set_closed(true), Err(Error::InvalidConfig(_))));
+    assert_eq!(screen.push(Message::new_simple("msg", 0, "first")), PushStatus::Queued);
+    assert_eq!(screen.append(five())[2..], [PushStatus::Dropped, PushStatus::Dropped, PushStatus::Dropped]); // nothing could make room
+
+    let mut screen = SceneBuilder::new().closed(false).capacity(10).overflow(Overflow::Sample).seed(17).build_virtual(6, 6).unwrap();
+    let statuses = screen.append((0..1000).map(|i| Message::new_simple("msg", 0, i)).collect());
+    let kept = statuses.iter().filter(|status| **status != PushStatus::Dropped).count();
+    assert!(kept > 20 && kept < 200);
+    let shown = spawned(&mut screen);
+    assert_eq!(shown.len(), 10);
+    assert!(shown.iter().any(|id| id.segments().next().unwrap().parse::<usize>().unwrap() >= 500)); // later lines still get a look in
+}
+
+#[test]
+fn samples_follow_the_seed() {
+    // what a Scene's pushes are decided by, same seed, same sample
+    let sample = |seed: u64| {
+	let backlog = Backlog::new(Some(4), Overflow::Sample, Some(seed));
+	(0..200).map(|_| backlog.admit()).collect::<Vec<Option<PushStatus>>>()
+    };
+    assert_eq!(sample(42), sample(42));
+    assert_ne!(sample(42), sample(43));
+
+    let builder = || SceneBuilder::new().closed(false).capacity(4).overflow(Overflow::Sample);
+    let push = |mut screen: testing::VirtualScreen| screen.append((0..200).map(|i| Message::new_simple("msg", 0, i)).collect());
+    assert_eq!(push(builder().seed(42).build_virtual(4, 4).unwrap()), push(builder().seed(42).build_virtual(4, 4).unwrap()));
+    assert_eq!(push(builder().rng(StdRng::seed_from_u64(7)).build_virtual(4, 4).unwrap()), push(builder().rng(StdRng::seed_from_u64(7)).build_virtual(4, 4).unwrap()));
+}
+
+#[test]
+fn blocked_pushes_wait_for_room() {
+    let mut scene = SceneBuilder::new().closed(false).capacity(2).overflow(Overflow::Block).speed(Duration::from_millis(1))
+	.build_with_backend(|| TestBackend::new(Some((4, 4)))).unwrap();
+    scene.start().unwrap();
+    for i in 0..20 {
+	assert_eq!(scene.push(Message::new_simple("msg", 0, i)), Ok(PushStatus::Queued));
+    }
+    scene.kill().unwrap();
+    assert_eq!(scene.push(Message::new_simple("msg", 0, "late")), Err(Error::Disconnected));
+}
+
+#[test]
+fn filler_rains_without_messages() {
+    let mut screen = SceneBuilder::new().closed(false).filler(Charset::binary()).seed(18).build_virtual(8, 8).unwrap();
+    screen.steps(20);
+    let cells: Vec<ColorChar> = (0..8).flat_map(|y| (0..8).map(move |x| (y, x))).filter_map(|(y, x)| screen.cell(y, x)).filter(|cell| cell.data != ' ' as u32).collect();
+    assert!(!cells.is_empty());
+    assert!(cells.iter().all(|cell| (cell.data == '0' as u32 || cell.data == '1' as u32) && cell.attr == A_DIM));
+
+    let mut screen = SceneBuilder::new().closed(false).max_padding(4).filler(".").filler_attr(A_BOLD).seed(19).build_virtual(12, 1).unwrap();
+    screen.push(Message::new_simple("ab", 0, "0"));
+    screen.push(Message::new_simple("cd", 0, "1"));
+    screen.steps(12);
+    let column: String = (0..12).map(|y| screen.row(y)).collect();
+    assert!(column.contains("ab") && column.contains("cd"), "{}", column);
+    assert!(column.contains('.'));
+    assert!((0..12).filter_map(|y| screen.cell(y, 0)).all(|cell| (cell.data == '.' as u32) == (cell.attr == A_BOLD)));
+
+    let mut screen = SceneBuilder::new().closed(false).filler(Charset::katakana()).seed(20).build_virtual(8, 8).unwrap();
+    screen.steps(20);
+    assert!(screen.dump().chars().any(|c| ('\u{FF66}'..='\u{FF9D}').contains(&c)));
+}
+
+#[test]
+fn charsets_hold_their_alphabets() {
+    assert_eq!(Charset::katakana().len(), 0xFF9D - 0xFF66 + 1);
+    assert!(Charset::katakana().contains('\u{FF66}') && Charset::katakana().contains('\u{FF9D}'));
+    assert_eq!(Charset::latin().len(), 52);
+    assert_eq!(Charset::digits().glyphs(), &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9']);
+    assert_eq!(Charset::binary().glyphs(), &['0', '1']);
+    assert_eq!(Charset::hex().len(), 16);
+    assert!(Charset::box_drawing().contains('─') && Charset::box_drawing().contains('╳'));
+    assert_eq!(Charset::dna().glyphs(), &['A', 'C', 'G', 'T']);
+
+    // user sets drop repeats, so every glyph is as likely as any other
+    assert_eq!(Charset::from("abba").glyphs(), &['a', 'b']);
+    assert_eq!(Charset::dna().union(Charset::from("ACU")).glyphs(), &['A', 'C', 'G', 'T', 'U']);
+    assert_eq!(Charset::hex(), Charset::digits().union("ABCDEF".into()));
+    assert_eq!("xyz".chars().collect::<Charset>(), Charset::new(vec!['x', 'y', 'z']));
+
+    let mut screen = SceneBuilder::new().closed(false).filler(Charset::dna()).seed(21).build_virtual(10, 10).unwrap();
+    screen.steps(30);
+    let glyphs: Vec<char> = screen.dump().chars().filter(|c| *c != ' ' && *c != '\n').collect();
+    assert!(!glyphs.is_empty());
+    assert!(glyphs.iter().all(|c| Charset::dna().contains(*c)), "{:?}", glyphs);
+}
+
+// cells showing the same streak row on both screens, which only differ if something re-rolled
+fn changed_cells(before: &testing::VirtualScreen, after: &testing::VirtualScreen, height: i32, width: i32) -> (usize, usize) {
+    let (mut kept, mut changed) = (0, 0);
+    for y in 0..height {
+	for x in 0..width {
+	    let (a, b) = (before.cell(y, x).unwrap(), after.cell(y, x).unwrap());
+	    if a.data == ' ' as u32 || b.data == ' ' as u32 {
+		continue;
+	    }
+	    if a.data == b.data {kept += 1} else {changed += 1}
+	}
+    }
+    (kept, changed)
+}
+
+#[test]
+fn filler_mutates_while_falling() {
+    let still = |mutation: f64| {
+	let mut screen = SceneBuilder::new().closed(false).filler(Charset::latin()).mutation(mutation).seed(22).build_virtual(10, 10).unwrap();
+	screen.steps(15);
+	let mut before = SceneBuilder::new().closed(false).filler(Charset::latin()).mutation(mutation).seed(22).build_virtual(10, 10).unwrap();
+	before.steps(14);
+	changed_cells(&before, &screen, 10, 10)
+    };
+    let (kept, changed) = still(0.0);
+    assert!(kept > 0 && changed == 0, "{} {}", kept, changed);
+    let (kept, changed) = still(1.0);
+    assert!(changed > kept, "{} {}", kept, changed);
+
+    // message text stays put unless asked, only ever showing glyphs from the charset when it doesn't
+    let mut screen = SceneBuilder::new().closed(true).max_padding(2).filler(Charset::digits()).mutation(1.0).seed(23).build_virtual(12, 1).unwrap();
+    screen.push(Message::new_simple("abcd", 0, "msg"));
+    screen.steps(12);
+    let column: String = (0..12).map(|y| screen.row(y)).collect();
+    assert!(column.chars().filter(|c| "abcd".contains(*c)).count() >= 4, "{}", column);
+    assert!(column.chars().all(|c| c == ' ' || c.is_ascii_digit() || "abcd".contains(c)), "{}", column);
+
+    screen.set_mutation(1.5).unwrap_err();
+    let mut screen = SceneBuilder::new().closed(true).max_padding(2).filler(Charset::digits()).mutation(1.0).mutate_messages(true).seed(23).build_virtual(12, 1).unwrap();
+    screen.push(Message::new_simple("abcd", 0, "msg"));
+    screen.steps(12);
+    let column: String = (0..12).map(|y| screen.row(y)).collect();
+    assert!(column.chars().all(|c| c == ' ' || c.is_ascii_digit()), "{}", column);
+    screen.set_mutation(0.0).unwrap();
+    screen.step();
+    let column: String = (0..12).map(|y| screen.row(y)).collect();
+    assert!(column.chars().any(|c| "abcd".contains(c)), "{}", column);
+}
+
+#[test]
+fn streaks_fade_from_a_bright_head() {
+    // one streak, 6 cells and a head long, with its head on row 8 after 10 ticks
+    let gradient = Gradient::new().head(A_BOLD).fade(A_NORMAL).fade(A_DIM);
+    let mut screen = SceneBuilder::new().closed(false).filler(".").streak_length(0.5, 0.51).gradient(gradient).seed(24).build_virtual(12, 1).unwrap();
+    screen.steps(10);
+    let attrs: Vec<Option<chtype>> = (0..12).map(|y| screen.cell(y, 0).filter(|cell| cell.data != ' ' as u32).map(|cell| cell.attr)).collect();
+    assert_eq!(attrs, vec![None, None, Some(A_DIM), Some(A_DIM), Some(A_DIM), Some(A_NORMAL), Some(A_NORMAL), Some(A_NORMAL), Some(A_BOLD), None, None, None]);
+
+    // message colours win unless told otherwise, taking the rest of the stage
+    let long = "m".repeat(48);
+    let styled = |gradient: Gradient| {
+	let mut screen = SceneBuilder::new().max_padding(0).streak_length(0.5, 0.51).gradient(gradient).seed(25).build_virtual(12, 1).unwrap();
+	screen.push(Message::new_simple(&long, 3, "long"));
+	screen.steps(10);
+	(2..9).map(|y| screen.cell(y, 0).unwrap().attr).collect::<Vec<chtype>>()
+    };
+    let gradient = Gradient::new().head(COLOR_PAIR(5) | A_BOLD).fade(COLOR_PAIR(6) | A_DIM);
+    assert_eq!(styled(gradient.clone()), [vec![COLOR_PAIR(3) | A_DIM; 6], vec![COLOR_PAIR(3) | A_BOLD]].concat());
+    assert_eq!(styled(gradient.message_colors(false)), [vec![COLOR_PAIR(6) | A_DIM; 6], vec![COLOR_PAIR(5) | A_BOLD]].concat());
+
+    // gaps between messages are left as they are, even inside a streak
+    let mut screen = SceneBuilder::new().max_padding(4).gradient(Gradient::new().head(A_REVERSE).fade(COLOR_PAIR(6) | A_REVERSE)).seed(26).build_virtual(12, 3).unwrap();
+    for i in 0..6 {
+	screen.push(Message::new_simple("abc", 0, i));
+    }
+    let (mut gaps, mut text) = (0, 0);
+    for _ in 0..30 {
+	screen.step();
+	for cell in (0..12).flat_map(|y| (0..3).map(move |x| (y, x))).filter_map(|(y, x)| screen.cell(y, x)) {
+	    if cell.data == ' ' as u32 {
+		assert_eq!(cell.attr, 0);
+		gaps += 1;
+	    } else {
+		assert!(cell.attr & A_REVERSE != 0);
+		text += 1;
+	    }
+	}
+    }
+    assert!(gaps > 0 && text > 0);
+}
+
+#[test]
+fn palettes_share_and_skip_pairs() {
+    let mut palette = Palette::new();
+    let green = palette.style(Style::new(COLOR_GREEN, COLOR_BLACK)).unwrap();
+    assert_eq!(green, COLOR_PAIR(1));
+    assert_eq!(palette.style(Style::new(COLOR_GREEN, COLOR_BLACK).bold()), Ok(COLOR_PAIR(1) | A_BOLD));
+    palette.reserve(2);
+    assert_eq!(palette.pair(COLOR_WHITE, -1), Ok(3));
+    assert_eq!(palette.pair(COLOR_GREEN, COLOR_BLACK), Ok(1));
+    assert!(matches!(palette.pair(-2, COLOR_BLACK), Err(Error::InvalidConfig(_))));
+
+    // 255 pairs, less the one reserved
+    for fg in 0..252 {
+	palette.pair(fg+8, COLOR_RED).unwrap();
+    }
+    assert_eq!(palette.pair(COLOR_RED, COLOR_RED), Err(Error::PaletteFull));
+    let mut pairs: Vec<i16> = palette.pairs().map(|(pair, _, _)| pair).collect();
+    pairs.sort_unstable();
+    assert_eq!(pairs, (1..256).filter(|pair| *pair != 2).collect::<Vec<i16>>());
+}
+
+#[test]
+fn scenes_set_up_their_palette() {
+    let mut palette = Palette::new();
+    let head = palette.style(Style::new(COLOR_WHITE, COLOR_BLACK).bold()).unwrap();
+    let mut backend = TestBackend::new(Some((5, 5)));
+    let (log, calls) = (backend.log(), backend.calls());
+    let mut scene = SceneBuilder::new().palette(palette).gradient(Gradient::new().head(head)).background(COLOR_BLUE)
+	.build_with_backend(move || backend).unwrap();
+    scene.init_pair(3, COLOR_RED, COLOR_BLACK).unwrap();
+    scene.init_pair(3, COLOR_RED, COLOR_WHITE).unwrap(); // hand-set pairs can change
+    assert_eq!(scene.init_pair(2, COLOR_RED, COLOR_BLACK), Err(Error::PairTaken(2))); // the background's
+    assert_eq!(scene.init_pair(1, COLOR_RED, COLOR_BLACK), Err(Error::PairTaken(1)));
+    assert!(matches!(scene.init_pair(256, COLOR_RED, COLOR_BLACK), Err(Error::InvalidConfig(_))));
+    assert!(matches!(scene.init_pair(4, -2, COLOR_BLACK), Err(Error::InvalidConfig(_))));
+    assert_eq!(scene.style(Style::new(COLOR_GREEN, COLOR_BLACK)), Ok(COLOR_PAIR(4)));
+    assert_eq!(scene.style(Style::new(COLOR_WHITE, COLOR_BLACK).dim()), Ok(COLOR_PAIR(1) | A_DIM));
+    scene.set_background(COLOR_WHITE).unwrap();
+    assert_eq!(scene.style(Style::new(COLOR_BLACK, COLOR_WHITE)), Ok(COLOR_PAIR(5))); // shared with the background
+    assert_eq!(scene.palette().pairs().count(), 4);
+    scene.kill().unwrap();
+    let mut pairs = log.lock().unwrap().pairs.clone();
+    pairs.sort_by_key(|&(pair, _, _)| pair); // the palette's own come in any order
+    assert_eq!(pairs, vec![(1, COLOR_WHITE, COLOR_BLACK), (2, COLOR_BLACK, COLOR_BLUE), (3, COLOR_RED, COLOR_BLACK), (3, COLOR_RED, COLOR_WHITE), (4, COLOR_GREEN, COLOR_BLACK), (5, COLOR_BLACK, COLOR_WHITE)]);
+    assert_eq!(calls.iter().collect::<Vec<Call>>(), vec![Call::Background(2), Call::Background(5)]);
+}
+
+#[test]
+fn palettes_fit_the_terminal() {
+    // a terminal with pairs 1 to 3, like a small one has 1 to 63
+    let backend = TestBackend::new(Some((5, 5))).max_pairs(4);
+    let log = backend.log();
+    let mut scene = SceneBuilder::new().build_with_backend(move || backend).unwrap();
+    assert_eq!(scene.palette().max_pairs(), 4);
+    for fg in 0..3 {
+	scene.style(Style::new(fg, COLOR_BLACK)).unwrap();
+    }
+    assert_eq!(scene.style(Style::new(COLOR_WHITE, COLOR_BLACK)), Err(Error::PaletteFull));
+    scene.kill().unwrap();
+    assert!(log.lock().unwrap().pairs.iter().all(|&(pair, _, _)| pair < 4));
+    assert!(log.lock().unwrap().pairs.contains(&(1, COLOR_BLACK, COLOR_BLACK))); // the background fits too
+
+    // pairs handed out before the terminal was known have to fit too
+    let mut palette = Palette::new();
+    for fg in 0..4 {
+	palette.pair(fg, COLOR_BLACK).unwrap();
+    }
+    let built = SceneBuilder::new().palette(palette.clone()).build_with_backend(|| TestBackend::new(Some((5, 5))).max_pairs(4));
+    assert_eq!(built.err(), Some(Error::PaletteFull));
+
+    // a terminal without colours draws without them, rather than failing
+    let backend = TestBackend::new(Some((5, 5))).max_pairs(0);
+    let log = backend.log();
+    let mut scene = SceneBuilder::new().palette(palette).build_with_backend(move || backend).unwrap();
+    assert_eq!(scene.init_pair(2, COLOR_RED, COLOR_BLACK), Ok(()));
+    assert_eq!(scene.style(Style::new(COLOR_RED, COLOR_BLACK).bold()), Ok(A_BOLD));
+    assert!(matches!(scene.style(Style::new(-2, COLOR_BLACK)), Err(Error::InvalidConfig(_))));
+    scene.kill().unwrap();
+    assert!(log.lock().unwrap().pairs.is_empty());
+}
+
+#[test]
+fn removed_messages_stop_falling() {
+    let on_screen = |screen: &testing::VirtualScreen, c: char| screen.dump().contains(c);
+    let mut screen = SceneBuilder::new().closed(false).max_padding(0).seed(27).build_virtual(12, 3).unwrap();
+    screen.push(Message::new_simple("AAAA", 0, "alerts/disk"));
+    screen.push(Message::new_simple("BBBB", 0, "alerts/cpu"));
+    screen.push(Message::new_simple("CCCC", 0, "status"));
+    let mut steps = 0;
+    while !(on_screen(&screen, 'A') && on_screen(&screen, 'B') && on_screen(&screen, 'C')) {
+	screen.step();
+	steps += 1;
+	assert!(steps < 50, "{}", screen.dump());
+    }
+    screen.remove("alerts/disk");
+    assert!(!on_screen(&screen, 'A') && on_screen(&screen, 'B'), "{}", screen.dump()); // straight away, not on the next tick
+    screen.remove_group("alerts");
+    assert!(!on_screen(&screen, 'B') && on_screen(&screen, 'C'));
+    screen.steps(3);
+    assert!(!on_screen(&screen, 'A') && !on_screen(&screen, 'B'));
+    screen.clear();
+    assert!(!on_screen(&screen, 'C'));
+    screen.steps(20);
+    assert_eq!(screen.dump().trim(), "");
+}